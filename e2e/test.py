@@ -117,10 +117,10 @@ def main():
 
     if check_response(response, "tools/list executes without error"):
         tools = response.get("result", {}).get("tools", [])
-        if len(tools) == 5:
-            print_test("tools/list returns 5 tools", True)
+        if len(tools) == 22:
+            print_test("tools/list returns 22 tools", True)
         else:
-            print_test(f"tools/list returns 5 tools (got {len(tools)})", False)
+            print_test(f"tools/list returns 22 tools (got {len(tools)})", False)
 
     # Test 2: List installed packages
     print("\nTesting list_installed_packages...")
@@ -529,6 +529,49 @@ def main():
             has_both_packages = "elm/json" in text and "elm/html" in text
             print_test("prompts/get (package-comparison) includes both packages", has_both_packages)
 
+    # Test 20: Test search_by_type
+    print("\nTesting search_by_type...")
+    response = send_request({
+        "jsonrpc": "2.0",
+        "id": 20,
+        "method": "tools/call",
+        "params": {
+            "name": "search_by_type",
+            "arguments": {
+                "signature": "(x -> y) -> List x -> List y"
+            }
+        }
+    })
+
+    if check_response(response, "search_by_type executes without error"):
+        content = response.get("result", {}).get("content", [{}])[0].get("text", "")
+        search_data = json.loads(strip_deprecation_warning(content))
+        results = search_data.get("results", [])
+        top_is_list_map = bool(results) and results[0]["module"] == "List" and results[0]["name"] == "map"
+        print_test("search_by_type ranks List.map first", top_is_list_map)
+
+    # Test 21: Test get_elm_module_docs
+    print("\nTesting get_elm_module_docs...")
+    response = send_request({
+        "jsonrpc": "2.0",
+        "id": 21,
+        "method": "tools/call",
+        "params": {
+            "name": "get_elm_module_docs",
+            "arguments": {
+                "author": "elm",
+                "name": "core",
+                "version": "1.0.5",
+                "module": "Maybe"
+            }
+        }
+    })
+
+    if check_response(response, "get_elm_module_docs executes without error"):
+        content = response.get("result", {}).get("content", [{}])[0].get("text", "")
+        markdown = strip_deprecation_warning(content)
+        print_test("get_elm_module_docs expands @docs", "@docs" not in markdown and "withDefault : a -> Maybe a -> a" in markdown)
+
     # Summary
     print(f"\n{YELLOW}Test Summary:{NC}")
     print(f"Tests passed: {GREEN}{tests_passed}{NC}")
@@ -0,0 +1,540 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A parsed Elm type annotation.
+///
+/// Type constructors keep whatever qualification they were written with, so
+/// annotations from docs.json look like `List.List a` while user-written
+/// queries are usually just `List a`. Use [`same_constructor`] to compare them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Type {
+    /// A type variable such as `a`, `msg` or `comparable`
+    Var { name: String },
+    /// A function from one argument to a result, `a -> b`
+    Lambda { arg: Box<Type>, result: Box<Type> },
+    /// A tuple; the empty tuple is the unit type `()`
+    Tuple { items: Vec<Type> },
+    /// A (possibly qualified) type constructor applied to its arguments
+    Constructor { name: String, args: Vec<Type> },
+    /// A record, or an extensible record when `extends` is set (`{ r | x : Int }`)
+    Record {
+        fields: Vec<(String, Type)>,
+        extends: Option<String>,
+    },
+}
+
+/// Compare two constructor names, ignoring module qualification when only one
+/// side is qualified (`List` matches `List.List`, `Decoder` matches `Json.Decode.Decoder`)
+pub fn same_constructor(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let a_qualified = a.contains('.');
+    let b_qualified = b.contains('.');
+    if a_qualified && b_qualified {
+        return false;
+    }
+    unqualified(a) == unqualified(b)
+}
+
+/// The last segment of a qualified name, e.g. `Decoder` for `Json.Decode.Decoder`
+pub fn unqualified(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// The built-in typeclass-like constraint implied by a variable name, if any
+pub fn constraint(var: &str) -> Option<&'static str> {
+    ["number", "comparable", "appendable", "compappend"]
+        .into_iter()
+        .find(|prefix| var.starts_with(prefix))
+}
+
+/// Types that every Elm module can refer to unqualified through the default imports
+const DEFAULT_IMPORTS: &[(&str, &str)] = &[
+    ("Basics.Int", "Int"),
+    ("Basics.Float", "Float"),
+    ("Basics.Bool", "Bool"),
+    ("Basics.Order", "Order"),
+    ("Basics.Never", "Never"),
+    ("List.List", "List"),
+    ("Maybe.Maybe", "Maybe"),
+    ("Result.Result", "Result"),
+    ("String.String", "String"),
+    ("Char.Char", "Char"),
+    ("Platform.Program", "Program"),
+    ("Platform.Cmd.Cmd", "Cmd"),
+    ("Platform.Sub.Sub", "Sub"),
+];
+
+impl Type {
+    /// Split a curried function type into its arguments and final result
+    pub fn flatten_lambda(&self) -> (Vec<&Type>, &Type) {
+        let mut args = Vec::new();
+        let mut current = self;
+        while let Type::Lambda { arg, result } = current {
+            args.push(arg.as_ref());
+            current = result.as_ref();
+        }
+        (args, current)
+    }
+
+    /// Drop module qualification the way Elm source would read inside
+    /// `module_name`: types from the module itself and from the default
+    /// imports (`Int`, `List`, `Maybe`, `Cmd`, ...) become unqualified.
+    pub fn shorten(&self, module_name: &str) -> Type {
+        match self {
+            Type::Var { .. } => self.clone(),
+            Type::Lambda { arg, result } => Type::Lambda {
+                arg: Box::new(arg.shorten(module_name)),
+                result: Box::new(result.shorten(module_name)),
+            },
+            Type::Tuple { items } => Type::Tuple {
+                items: items.iter().map(|t| t.shorten(module_name)).collect(),
+            },
+            Type::Constructor { name, args } => {
+                let short = match DEFAULT_IMPORTS.iter().find(|(full, _)| full == name) {
+                    Some((_, short)) => short.to_string(),
+                    None => match name.rsplit_once('.') {
+                        Some((module, short)) if module == module_name => short.to_string(),
+                        _ => name.clone(),
+                    },
+                };
+                Type::Constructor {
+                    name: short,
+                    args: args.iter().map(|t| t.shorten(module_name)).collect(),
+                }
+            }
+            Type::Record { fields, extends } => Type::Record {
+                fields: fields
+                    .iter()
+                    .map(|(name, t)| (name.clone(), t.shorten(module_name)))
+                    .collect(),
+                extends: extends.clone(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_type(f, self, Context::Top)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Top,
+    LambdaArg,
+    ConstructorArg,
+}
+
+fn write_type(f: &mut fmt::Formatter<'_>, typ: &Type, context: Context) -> fmt::Result {
+    match typ {
+        Type::Var { name } => write!(f, "{name}"),
+        Type::Lambda { arg, result } => {
+            let parens = context != Context::Top;
+            if parens {
+                write!(f, "(")?;
+            }
+            write_type(f, arg, Context::LambdaArg)?;
+            write!(f, " -> ")?;
+            write_type(f, result, Context::Top)?;
+            if parens {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Tuple { items } => {
+            if items.is_empty() {
+                return write!(f, "()");
+            }
+            write!(f, "( ")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_type(f, item, Context::Top)?;
+            }
+            write!(f, " )")
+        }
+        Type::Constructor { name, args } => {
+            let parens = context == Context::ConstructorArg && !args.is_empty();
+            if parens {
+                write!(f, "(")?;
+            }
+            write!(f, "{name}")?;
+            for arg in args {
+                write!(f, " ")?;
+                write_type(f, arg, Context::ConstructorArg)?;
+            }
+            if parens {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Record { fields, extends } => {
+            if fields.is_empty() && extends.is_none() {
+                return write!(f, "{{}}");
+            }
+            write!(f, "{{ ")?;
+            if let Some(base) = extends {
+                write!(f, "{base} | ")?;
+            }
+            for (i, (name, field_type)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{name} : ")?;
+                write_type(f, field_type, Context::Top)?;
+            }
+            write!(f, " }}")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Upper(String),
+    Lower(String),
+    Arrow,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Pipe,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '{' => {
+                tokens.push(Token::LBrace);
+                i += 1;
+            }
+            '}' => {
+                tokens.push(Token::RBrace);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            ':' => {
+                tokens.push(Token::Colon);
+                i += 1;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                i += 1;
+            }
+            '-' if chars.get(i + 1) == Some(&'>') => {
+                tokens.push(Token::Arrow);
+                i += 2;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                // Qualified names are runs of identifiers joined by dots
+                loop {
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_alphabetic() {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let word: String = chars[start..i].iter().collect();
+                let last = unqualified(&word);
+                if last.starts_with(|ch: char| ch.is_uppercase()) {
+                    tokens.push(Token::Upper(word));
+                } else if word.contains('.') {
+                    return Err(format!("Unexpected qualified variable '{word}'"));
+                } else {
+                    tokens.push(Token::Lower(word));
+                }
+            }
+            _ => return Err(format!("Unexpected character '{c}' in type annotation")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {expected:?} but found {token:?}")),
+            None => Err(format!("Expected {expected:?} but reached end of input")),
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<Type, String> {
+        let arg = self.parse_application()?;
+        if self.peek() == Some(&Token::Arrow) {
+            self.pos += 1;
+            let result = self.parse_type()?;
+            Ok(Type::Lambda {
+                arg: Box::new(arg),
+                result: Box::new(result),
+            })
+        } else {
+            Ok(arg)
+        }
+    }
+
+    fn parse_application(&mut self) -> Result<Type, String> {
+        if let Some(Token::Upper(name)) = self.peek().cloned() {
+            self.pos += 1;
+            let mut args = Vec::new();
+            while self.starts_atom() {
+                args.push(self.parse_atom()?);
+            }
+            Ok(Type::Constructor { name, args })
+        } else {
+            self.parse_atom()
+        }
+    }
+
+    fn starts_atom(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(Token::Upper(_) | Token::Lower(_) | Token::LParen | Token::LBrace)
+        )
+    }
+
+    fn parse_atom(&mut self) -> Result<Type, String> {
+        match self.next() {
+            Some(Token::Lower(name)) => Ok(Type::Var { name }),
+            Some(Token::Upper(name)) => Ok(Type::Constructor { name, args: vec![] }),
+            Some(Token::LParen) => {
+                if self.peek() == Some(&Token::RParen) {
+                    self.pos += 1;
+                    return Ok(Type::Tuple { items: vec![] });
+                }
+                let mut items = vec![self.parse_type()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    items.push(self.parse_type()?);
+                }
+                self.expect(Token::RParen)?;
+                if items.len() == 1 {
+                    Ok(items.remove(0))
+                } else {
+                    Ok(Type::Tuple { items })
+                }
+            }
+            Some(Token::LBrace) => self.parse_record(),
+            Some(token) => Err(format!("Unexpected {token:?} in type annotation")),
+            None => Err("Unexpected end of type annotation".to_string()),
+        }
+    }
+
+    fn parse_record(&mut self) -> Result<Type, String> {
+        if self.peek() == Some(&Token::RBrace) {
+            self.pos += 1;
+            return Ok(Type::Record {
+                fields: vec![],
+                extends: None,
+            });
+        }
+
+        let mut extends = None;
+        if let (Some(Token::Lower(name)), Some(Token::Pipe)) =
+            (self.tokens.get(self.pos), self.tokens.get(self.pos + 1))
+        {
+            extends = Some(name.clone());
+            self.pos += 2;
+        }
+
+        let mut fields = Vec::new();
+        loop {
+            let field_name = match self.next() {
+                Some(Token::Lower(name)) => name,
+                Some(token) => return Err(format!("Expected record field but found {token:?}")),
+                None => return Err("Unexpected end of record type".to_string()),
+            };
+            if fields.iter().any(|(name, _)| *name == field_name) {
+                return Err(format!("Duplicate record field '{field_name}'"));
+            }
+            self.expect(Token::Colon)?;
+            fields.push((field_name, self.parse_type()?));
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RBrace) => break,
+                Some(token) => return Err(format!("Expected ',' or '}}' but found {token:?}")),
+                None => return Err("Unexpected end of record type".to_string()),
+            }
+        }
+
+        Ok(Type::Record { fields, extends })
+    }
+}
+
+/// Parse an Elm type annotation such as `(a -> b) -> List a -> List b`.
+///
+/// A leading `name :` is accepted and ignored so full signatures copied from
+/// source code can be used directly.
+pub fn parse(input: &str) -> Result<Type, String> {
+    let mut tokens = tokenize(input)?;
+    if let [Token::Lower(_), Token::Colon, ..] = tokens.as_slice() {
+        tokens.drain(..2);
+    }
+    if tokens.is_empty() {
+        return Err("Empty type annotation".to_string());
+    }
+
+    let mut parser = Parser { tokens, pos: 0 };
+    let typ = parser.parse_type()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected {token:?} after end of type annotation"));
+    }
+    Ok(typ)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Type {
+        Type::Var {
+            name: name.to_string(),
+        }
+    }
+
+    fn con(name: &str, args: Vec<Type>) -> Type {
+        Type::Constructor {
+            name: name.to_string(),
+            args,
+        }
+    }
+
+    fn lambda(arg: Type, result: Type) -> Type {
+        Type::Lambda {
+            arg: Box::new(arg),
+            result: Box::new(result),
+        }
+    }
+
+    #[test]
+    fn test_parse_function() {
+        let parsed = parse("(a -> b) -> List.List a -> List.List b").unwrap();
+        assert_eq!(
+            parsed,
+            lambda(
+                lambda(var("a"), var("b")),
+                lambda(
+                    con("List.List", vec![var("a")]),
+                    con("List.List", vec![var("b")])
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_records_and_tuples() {
+        let parsed = parse("{ r | name : String, tags : List (Maybe a) } -> ( Int, () )").unwrap();
+        let (args, result) = parsed.flatten_lambda();
+        assert_eq!(
+            args[0],
+            &Type::Record {
+                fields: vec![
+                    ("name".to_string(), con("String", vec![])),
+                    (
+                        "tags".to_string(),
+                        con("List", vec![con("Maybe", vec![var("a")])])
+                    ),
+                ],
+                extends: Some("r".to_string()),
+            }
+        );
+        assert_eq!(
+            result,
+            &Type::Tuple {
+                items: vec![con("Int", vec![]), Type::Tuple { items: vec![] }]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_strips_name() {
+        assert_eq!(
+            parse("identity : a -> a").unwrap(),
+            parse("a -> a").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("List (a").is_err());
+        assert!(parse("{ x : }").is_err());
+        assert!(parse("a -> ").is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for source in [
+            "(a -> b) -> List.List a -> List.List b",
+            "Json.Decode.Decoder (Maybe.Maybe a) -> { b | x : Basics.Int }",
+            "( a, b ) -> () -> {}",
+            "Dict.Dict comparable (List.List (a -> b))",
+        ] {
+            let parsed = parse(source).unwrap();
+            assert_eq!(parsed.to_string(), source);
+            assert_eq!(parse(&parsed.to_string()).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn test_shorten() {
+        let parsed =
+            parse("Json.Decode.Decoder a -> Dict.Dict String.String (Platform.Cmd.Cmd msg)")
+                .unwrap();
+        assert_eq!(
+            parsed.shorten("Json.Decode").to_string(),
+            "Decoder a -> Dict.Dict String (Cmd msg)"
+        );
+    }
+
+    #[test]
+    fn test_same_constructor() {
+        assert!(same_constructor("List", "List.List"));
+        assert!(same_constructor("Json.Decode.Decoder", "Decoder"));
+        assert!(!same_constructor("Array.Array", "List.List"));
+        assert!(!same_constructor("Json.Decode.Value", "Json.Encode.Value"));
+    }
+}
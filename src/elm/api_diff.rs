@@ -0,0 +1,476 @@
+use crate::elm::annotation::{constraint, Type};
+use crate::elm::fetcher::Module;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// How big a change is, following Elm's semantic versioning rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Magnitude {
+    Patch,
+    Minor,
+    Major,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiDiff {
+    pub magnitude: Magnitude,
+    pub added_modules: Vec<String>,
+    pub removed_modules: Vec<String>,
+    pub changed_modules: Vec<ModuleDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleDiff {
+    pub name: String,
+    pub magnitude: Magnitude,
+    pub added: Vec<ExportSummary>,
+    pub removed: Vec<ExportSummary>,
+    pub changed: Vec<ExportChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub name: String,
+    pub kind: &'static str,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportChange {
+    pub name: String,
+    pub kind: &'static str,
+    pub before: String,
+    pub after: String,
+    /// Union constructors only in the new version
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cases_added: Vec<String>,
+    /// Union constructors only in the old version
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cases_removed: Vec<String>,
+}
+
+/// One export reduced to what matters for comparison
+struct Export {
+    kind: &'static str,
+    /// Human-readable signature, with local and default-import types unqualified
+    signature: String,
+    /// Signature with type variables renamed canonically, so `a -> a` and
+    /// `x -> x` compare equal the way `elm diff` treats them
+    key: String,
+    cases: Vec<String>,
+}
+
+/// Compare the docs of two versions of a package, the way `elm diff` does:
+/// removing or changing anything is MAJOR, only adding things is MINOR, and
+/// an unchanged API is PATCH.
+pub fn diff(old: &[Module], new: &[Module]) -> ApiDiff {
+    let old_modules: BTreeMap<&str, &Module> = old.iter().map(|m| (m.name.as_str(), m)).collect();
+    let new_modules: BTreeMap<&str, &Module> = new.iter().map(|m| (m.name.as_str(), m)).collect();
+
+    let removed_modules: Vec<String> = old_modules
+        .keys()
+        .filter(|name| !new_modules.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    let added_modules: Vec<String> = new_modules
+        .keys()
+        .filter(|name| !old_modules.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    let changed_modules: Vec<ModuleDiff> = old_modules
+        .iter()
+        .filter_map(|(name, old_module)| {
+            let new_module = new_modules.get(name)?;
+            let module_diff = diff_module(old_module, new_module);
+            let unchanged = module_diff.added.is_empty()
+                && module_diff.removed.is_empty()
+                && module_diff.changed.is_empty();
+            (!unchanged).then_some(module_diff)
+        })
+        .collect();
+
+    let magnitude = if !removed_modules.is_empty() {
+        Magnitude::Major
+    } else {
+        let added = if added_modules.is_empty() {
+            Magnitude::Patch
+        } else {
+            Magnitude::Minor
+        };
+        changed_modules
+            .iter()
+            .map(|m| m.magnitude)
+            .fold(added, Magnitude::max)
+    };
+
+    ApiDiff {
+        magnitude,
+        added_modules,
+        removed_modules,
+        changed_modules,
+    }
+}
+
+fn diff_module(old: &Module, new: &Module) -> ModuleDiff {
+    let old_exports = exports(old);
+    let new_exports = exports(new);
+
+    let summary = |name: &str, export: &Export| ExportSummary {
+        name: name.to_string(),
+        kind: export.kind,
+        signature: export.signature.clone(),
+    };
+
+    let removed: Vec<ExportSummary> = old_exports
+        .iter()
+        .filter(|(name, _)| !new_exports.contains_key(*name))
+        .map(|(name, export)| summary(name, export))
+        .collect();
+    let added: Vec<ExportSummary> = new_exports
+        .iter()
+        .filter(|(name, _)| !old_exports.contains_key(*name))
+        .map(|(name, export)| summary(name, export))
+        .collect();
+    let changed: Vec<ExportChange> = old_exports
+        .iter()
+        .filter_map(|(name, before)| {
+            let after = new_exports.get(name)?;
+            if before.kind == after.kind && before.key == after.key {
+                return None;
+            }
+            Some(ExportChange {
+                name: name.clone(),
+                kind: after.kind,
+                before: before.signature.clone(),
+                after: after.signature.clone(),
+                cases_added: after
+                    .cases
+                    .iter()
+                    .filter(|case| !before.cases.contains(case))
+                    .cloned()
+                    .collect(),
+                cases_removed: before
+                    .cases
+                    .iter()
+                    .filter(|case| !after.cases.contains(case))
+                    .cloned()
+                    .collect(),
+            })
+        })
+        .collect();
+
+    let magnitude = if !removed.is_empty() || !changed.is_empty() {
+        Magnitude::Major
+    } else if !added.is_empty() {
+        Magnitude::Minor
+    } else {
+        Magnitude::Patch
+    };
+
+    ModuleDiff {
+        name: new.name.clone(),
+        magnitude,
+        added,
+        removed,
+        changed,
+    }
+}
+
+fn exports(module: &Module) -> BTreeMap<String, Export> {
+    let mut exports = BTreeMap::new();
+    let show = |typ: &Option<Type>, raw: &str| match typ {
+        Some(typ) => typ.shorten(&module.name).to_string(),
+        None => raw.to_string(),
+    };
+
+    for union in &module.unions {
+        let mut canonical = Canonical::default();
+        let args: Vec<String> = union.args.iter().map(|a| canonical.var(a)).collect();
+        let (shown, mut keys): (Vec<String>, Vec<String>) =
+            if union.parsed_cases.len() == union.cases.len() {
+                union
+                    .parsed_cases
+                    .iter()
+                    .map(|case| {
+                        let constructor = Type::Constructor {
+                            name: case.name.clone(),
+                            args: case.args.clone(),
+                        };
+                        (
+                            constructor.shorten(&module.name).to_string(),
+                            canonical.typ(&constructor).to_string(),
+                        )
+                    })
+                    .unzip()
+            } else {
+                union
+                    .cases
+                    .iter()
+                    .map(|case| (case.join(" "), case.join(" ")))
+                    .unzip()
+            };
+        // Reordering constructors doesn't change the API
+        keys.sort();
+
+        let head = std::iter::once(union.name.as_str())
+            .chain(union.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let signature = if shown.is_empty() {
+            format!("type {head}")
+        } else {
+            format!("type {head} = {}", shown.join(" | "))
+        };
+        exports.insert(
+            union.name.clone(),
+            Export {
+                kind: "union",
+                signature,
+                key: format!("{} = {}", args.join(" "), keys.join(" | ")),
+                cases: union
+                    .cases
+                    .iter()
+                    .filter_map(|c| c.first().cloned())
+                    .collect(),
+            },
+        );
+    }
+
+    for alias in &module.aliases {
+        let mut canonical = Canonical::default();
+        let args: Vec<String> = alias.args.iter().map(|a| canonical.var(a)).collect();
+        let body = match &alias.parsed_type {
+            Some(typ) => canonical.typ(typ).to_string(),
+            None => alias.type_annotation.clone(),
+        };
+        let head = std::iter::once(alias.name.as_str())
+            .chain(alias.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        exports.insert(
+            alias.name.clone(),
+            Export {
+                kind: "alias",
+                signature: format!(
+                    "type alias {head} = {}",
+                    show(&alias.parsed_type, &alias.type_annotation)
+                ),
+                key: format!("{} = {body}", args.join(" ")),
+                cases: vec![],
+            },
+        );
+    }
+
+    for value in &module.values {
+        exports.insert(
+            value.name.clone(),
+            Export {
+                kind: "value",
+                signature: format!(
+                    "{} : {}",
+                    value.name,
+                    show(&value.parsed_type, &value.type_annotation)
+                ),
+                key: canonical_annotation(&value.parsed_type, &value.type_annotation),
+                cases: vec![],
+            },
+        );
+    }
+
+    for binop in &module.binops {
+        exports.insert(
+            format!("({})", binop.name),
+            Export {
+                kind: "binop",
+                signature: format!(
+                    "({}) : {}",
+                    binop.name,
+                    show(&binop.parsed_type, &binop.type_annotation)
+                ),
+                // Precedence and associativity change how existing code parses
+                key: format!(
+                    "{} infix{} {}",
+                    canonical_annotation(&binop.parsed_type, &binop.type_annotation),
+                    binop.associativity,
+                    binop.precedence
+                ),
+                cases: vec![],
+            },
+        );
+    }
+
+    exports
+}
+
+fn canonical_annotation(parsed: &Option<Type>, raw: &str) -> String {
+    match parsed {
+        Some(typ) => Canonical::default().typ(typ).to_string(),
+        None => raw.to_string(),
+    }
+}
+
+/// Renames type variables in order of first appearance, keeping the
+/// `number`/`comparable`/`appendable`/`compappend` prefix since changing a
+/// constraint does change the API
+#[derive(Default)]
+struct Canonical {
+    names: HashMap<String, String>,
+}
+
+impl Canonical {
+    fn var(&mut self, name: &str) -> String {
+        let next = self.names.len();
+        self.names
+            .entry(name.to_string())
+            .or_insert_with(|| format!("{}{next}", constraint(name).unwrap_or("t")))
+            .clone()
+    }
+
+    fn typ(&mut self, typ: &Type) -> Type {
+        match typ {
+            Type::Var { name } => Type::Var {
+                name: self.var(name),
+            },
+            Type::Lambda { arg, result } => Type::Lambda {
+                arg: Box::new(self.typ(arg)),
+                result: Box::new(self.typ(result)),
+            },
+            Type::Tuple { items } => Type::Tuple {
+                items: items.iter().map(|t| self.typ(t)).collect(),
+            },
+            Type::Constructor { name, args } => Type::Constructor {
+                name: name.clone(),
+                args: args.iter().map(|t| self.typ(t)).collect(),
+            },
+            Type::Record { fields, extends } => {
+                let extends = extends.as_ref().map(|base| self.var(base));
+                // Field order in a record type is not significant
+                let mut fields: Vec<(String, Type)> = fields
+                    .iter()
+                    .map(|(name, t)| (name.clone(), self.typ(t)))
+                    .collect();
+                fields.sort_by(|a, b| a.0.cmp(&b.0));
+                Type::Record { fields, extends }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::fetcher::parse_docs;
+
+    fn module(unions: &str, aliases: &str, values: &str) -> Vec<Module> {
+        parse_docs(&format!(
+            r#"[{{"name": "Shape", "comment": "", "unions": [{unions}], "aliases": [{aliases}], "values": [{values}], "binops": []}}]"#
+        ))
+        .unwrap()
+    }
+
+    fn value(name: &str, annotation: &str) -> String {
+        format!(r#"{{"name": "{name}", "comment": "", "type": "{annotation}"}}"#)
+    }
+
+    const SHAPE: &str = r#"{"name": "Shape", "comment": "", "args": [], "cases": [["Circle", ["Basics.Float"]], ["Square", ["Basics.Float"]]]}"#;
+
+    #[test]
+    fn test_diff_unchanged_is_patch() {
+        let old = module(
+            SHAPE,
+            "",
+            &value("map", "(a -> b) -> List.List a -> List.List b"),
+        );
+        // Renaming type variables and reordering constructors is not a change
+        let new = module(
+            r#"{"name": "Shape", "comment": "", "args": [], "cases": [["Square", ["Basics.Float"]], ["Circle", ["Basics.Float"]]]}"#,
+            "",
+            &value("map", "(x -> y) -> List.List x -> List.List y"),
+        );
+        let result = diff(&old, &new);
+        assert_eq!(result.magnitude, Magnitude::Patch);
+        assert!(result.changed_modules.is_empty());
+    }
+
+    #[test]
+    fn test_diff_additions_are_minor() {
+        let old = module(SHAPE, "", &value("area", "Shape.Shape -> Basics.Float"));
+        let new = module(
+            SHAPE,
+            r#"{"name": "Point", "comment": "", "args": [], "type": "{ x : Basics.Float, y : Basics.Float }"}"#,
+            &[
+                value("area", "Shape.Shape -> Basics.Float"),
+                value("center", "Shape.Shape -> Shape.Point"),
+            ]
+            .join(","),
+        );
+        let result = diff(&old, &new);
+        assert_eq!(result.magnitude, Magnitude::Minor);
+        let added: Vec<&str> = result.changed_modules[0]
+            .added
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(added, vec!["Point", "center"]);
+        assert_eq!(
+            result.changed_modules[0].added[1].signature,
+            "center : Shape -> Point"
+        );
+    }
+
+    #[test]
+    fn test_diff_changes_are_major() {
+        let old = module(
+            SHAPE,
+            r#"{"name": "Point", "comment": "", "args": [], "type": "{ x : Basics.Float, y : Basics.Float }"}"#,
+            &[
+                value("area", "Shape.Shape -> Basics.Float"),
+                value("sum", "List.List number -> number"),
+            ]
+            .join(","),
+        );
+        let new = module(
+            r#"{"name": "Shape", "comment": "", "args": [], "cases": [["Circle", ["Basics.Float"]], ["Triangle", ["Basics.Float", "Basics.Float"]]]}"#,
+            r#"{"name": "Point", "comment": "", "args": [], "type": "{ x : Basics.Int, y : Basics.Int }"}"#,
+            &value("sum", "List.List a -> a"),
+        );
+        let result = diff(&old, &new);
+        assert_eq!(result.magnitude, Magnitude::Major);
+
+        let module_diff = &result.changed_modules[0];
+        assert_eq!(module_diff.removed[0].name, "area");
+        let changed: Vec<&str> = module_diff
+            .changed
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(changed, vec!["Point", "Shape", "sum"]);
+
+        let shape = &module_diff.changed[1];
+        assert_eq!(shape.cases_added, vec!["Triangle"]);
+        assert_eq!(shape.cases_removed, vec!["Square"]);
+        assert_eq!(
+            shape.after,
+            "type Shape = Circle Float | Triangle Float Float"
+        );
+        assert_eq!(
+            module_diff.changed[0].after,
+            "type alias Point = { x : Int, y : Int }"
+        );
+    }
+
+    #[test]
+    fn test_diff_modules() {
+        let old = module("", "", "");
+        let mut new = module("", "", "");
+        new[0].name = "Geometry".to_string();
+        let result = diff(&old, &new);
+        assert_eq!(result.magnitude, Magnitude::Major);
+        assert_eq!(result.removed_modules, vec!["Shape"]);
+        assert_eq!(result.added_modules, vec!["Geometry"]);
+
+        let result = diff(&old, &[old.clone(), new].concat());
+        assert_eq!(result.magnitude, Magnitude::Minor);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::elm::registry;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Environment variable overriding where downloaded registry data is cached
+pub const CACHE_DIR_ENV: &str = "ELM_PACKAGE_MCP_CACHE_DIR";
+
+/// Environment variable overriding how long cached registry data is used
+/// without revalidating, in seconds
+pub const CACHE_TTL_ENV: &str = "ELM_PACKAGE_MCP_CACHE_TTL";
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The cache directory: `$ELM_PACKAGE_MCP_CACHE_DIR`, otherwise
+/// `elm-package-mcp-server` under `$XDG_CACHE_HOME` or `~/.cache`
+pub fn cache_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os(CACHE_DIR_ENV).filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    let base = match std::env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home_dir = std::env::var("HOME")
+                .map_err(|_| "Could not determine HOME directory".to_string())?;
+            PathBuf::from(home_dir).join(".cache")
+        }
+    };
+    Ok(base.join("elm-package-mcp-server"))
+}
+
+/// How long cached registry data is fresh, from `$ELM_PACKAGE_MCP_CACHE_TTL`
+pub fn ttl() -> Duration {
+    std::env::var(CACHE_TTL_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TTL)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Unix timestamp of the last successful fetch or revalidation
+    fetched_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub body: String,
+    /// Unix timestamp of the last time the body was confirmed current
+    pub fetched_at: i64,
+    /// True when the registry couldn't be reached and an expired copy was used
+    pub stale: bool,
+}
+
+/// A disk cache for registry documents, revalidated with ETag/Last-Modified
+/// once older than its TTL
+pub struct HttpCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl HttpCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        HttpCache { dir, ttl }
+    }
+
+    /// The cache configured from the environment
+    pub fn from_env() -> Result<Self, String> {
+        Ok(HttpCache::new(cache_dir()?, ttl()))
+    }
+
+    /// Get `url`, stored under `key`. Fresh copies are returned without a
+    /// request; expired ones are revalidated; if the registry is unreachable
+    /// the last good copy is returned with `stale` set.
+    pub fn get(&self, key: &str, url: &str) -> Result<CachedResponse, String> {
+        let body_path = self.dir.join(key);
+        let meta_path = self.dir.join(format!("{key}.meta.json"));
+        let now = chrono::Utc::now().timestamp();
+
+        let cached = fs::read_to_string(&meta_path)
+            .ok()
+            .and_then(|meta| serde_json::from_str::<CacheMeta>(&meta).ok())
+            .filter(|meta| meta.url == url)
+            .and_then(|meta| fs::read_to_string(&body_path).ok().map(|body| (meta, body)));
+
+        if let Some((meta, body)) = &cached {
+            if now - meta.fetched_at < self.ttl.as_secs() as i64 {
+                return Ok(CachedResponse {
+                    body: body.clone(),
+                    fetched_at: meta.fetched_at,
+                    stale: false,
+                });
+            }
+        }
+
+        match self.revalidate(url, cached.as_ref().map(|(meta, _)| meta)) {
+            Ok((mut meta, body)) => {
+                meta.fetched_at = now;
+                let body = match body {
+                    Some(body) => body,
+                    // 304 Not Modified, so the cached body is current
+                    None => cached.map(|(_, body)| body).unwrap_or_default(),
+                };
+                // Failing to write the cache shouldn't fail the request. Keys
+                // may name subdirectories.
+                let parent = body_path.parent().unwrap_or(&self.dir);
+                let _ = fs::create_dir_all(parent).and_then(|_| {
+                    fs::write(&body_path, &body)?;
+                    fs::write(&meta_path, serde_json::to_string(&meta).unwrap_or_default())
+                });
+                Ok(CachedResponse {
+                    body,
+                    fetched_at: now,
+                    stale: false,
+                })
+            }
+            Err(e) => match cached {
+                Some((meta, body)) => Ok(CachedResponse {
+                    body,
+                    fetched_at: meta.fetched_at,
+                    stale: true,
+                }),
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Fetch `url`, conditionally if there is a cached copy. Returns the new
+    /// metadata and the body, or no body when the server answered 304.
+    fn revalidate(
+        &self,
+        url: &str,
+        cached: Option<&CacheMeta>,
+    ) -> Result<(CacheMeta, Option<String>), String> {
+        let mut request = registry::client()?.get(url);
+        if let Some(meta) = cached {
+            if let Some(etag) = &meta.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let mut meta = CacheMeta {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            fetched_at: 0,
+        };
+
+        if let (StatusCode::NOT_MODIFIED, Some(previous)) = (response.status(), cached) {
+            meta.etag = meta.etag.or_else(|| previous.etag.clone());
+            meta.last_modified = meta
+                .last_modified
+                .or_else(|| previous.last_modified.clone());
+            return Ok((meta, None));
+        }
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to fetch {}: HTTP {}",
+                url,
+                response.status()
+            ));
+        }
+
+        let body = response
+            .text()
+            .map_err(|e| format!("Failed to read response from {}: {}", url, e))?;
+        Ok((meta, Some(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::registry::tests::serve_with;
+    use crate::elm::test_dir::TestDir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cache_fresh_revalidated_and_stale() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let conditional = Arc::new(AtomicUsize::new(0));
+        let (requests_seen, conditional_seen) = (requests.clone(), conditional.clone());
+        let base_url = serve_with(move |request| {
+            requests_seen.fetch_add(1, Ordering::SeqCst);
+            if request.header("If-None-Match") == Some("\"v1\"") {
+                conditional_seen.fetch_add(1, Ordering::SeqCst);
+                return (304, vec![], String::new());
+            }
+            (
+                200,
+                vec![("ETag".to_string(), "\"v1\"".to_string())],
+                "[]".to_string(),
+            )
+        });
+        let url = format!("{base_url}/search.json");
+        let dir = TestDir::new("cache-search");
+
+        // First request downloads, second is served from disk
+        let fresh = HttpCache::new(dir.to_path_buf(), DEFAULT_TTL);
+        assert_eq!(fresh.get("search.json", &url).unwrap().body, "[]");
+        assert_eq!(fresh.get("search.json", &url).unwrap().body, "[]");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // An expired copy is revalidated with its ETag
+        let expired = HttpCache::new(dir.to_path_buf(), Duration::ZERO);
+        let revalidated = expired.get("search.json", &url).unwrap();
+        assert_eq!(revalidated.body, "[]");
+        assert!(!revalidated.stale);
+        assert_eq!(conditional.load(Ordering::SeqCst), 1);
+
+        // An unreachable registry falls back to the last good copy
+        let offline_url = "http://127.0.0.1:9/search.json";
+        let offline = HttpCache::new(dir.to_path_buf(), Duration::ZERO);
+        fs::write(
+            dir.join("search.json.meta.json"),
+            fs::read_to_string(dir.join("search.json.meta.json"))
+                .unwrap()
+                .replace(&url, offline_url),
+        )
+        .unwrap();
+        let stale = offline.get("search.json", offline_url).unwrap();
+        assert_eq!(stale.body, "[]");
+        assert!(stale.stale);
+    }
+
+    #[test]
+    fn test_cache_error_without_copy() {
+        let dir = TestDir::new("cache-missing");
+        let cache = HttpCache::new(dir.to_path_buf(), DEFAULT_TTL);
+        assert!(cache
+            .get("search.json", "http://127.0.0.1:9/search.json")
+            .is_err());
+    }
+}
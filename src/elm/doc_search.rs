@@ -0,0 +1,358 @@
+use crate::elm::fetcher::Module;
+use crate::elm::PackageInfo;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Standard BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Names are short but the most telling part of an export, so their terms
+/// count this many times over terms from the doc comment
+const NAME_WEIGHT: u32 = 3;
+
+const SNIPPET_LENGTH: usize = 160;
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "into", "is", "it",
+    "its", "of", "on", "or", "that", "the", "this", "to", "with", "you", "your",
+];
+
+/// One searchable export (or module) and its documentation
+#[derive(Debug, Clone)]
+struct Document {
+    package: String,
+    version: String,
+    module: String,
+    name: Option<String>,
+    kind: &'static str,
+    type_annotation: Option<String>,
+    comment: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocHit {
+    pub package: String,
+    pub version: String,
+    pub module: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub kind: &'static str,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_annotation: Option<String>,
+    pub score: f64,
+    pub snippet: String,
+}
+
+/// A BM25-ranked inverted index over module and export doc comments
+#[derive(Clone, Default)]
+pub struct DocIndex {
+    documents: Vec<Document>,
+    lengths: Vec<u32>,
+    /// term -> (document index, term frequency)
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    total_length: u64,
+}
+
+impl DocIndex {
+    pub fn new() -> Self {
+        DocIndex::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Index every module, union, alias, value and binop in a package
+    pub fn add_package(&mut self, package: &PackageInfo, modules: &[Module]) {
+        let package_name = format!("{}/{}", package.author, package.name);
+        for module in modules {
+            let mut add = |name: Option<&str>,
+                           kind: &'static str,
+                           type_annotation: Option<String>,
+                           comment: &str| {
+                self.add(Document {
+                    package: package_name.clone(),
+                    version: package.version.clone(),
+                    module: module.name.clone(),
+                    name: name.map(|n| n.to_string()),
+                    kind,
+                    type_annotation,
+                    comment: comment.trim().to_string(),
+                })
+            };
+
+            if !module.comment.trim().is_empty() {
+                add(None, "module", None, &module.comment);
+            }
+            for union in &module.unions {
+                add(Some(&union.name), "union", None, &union.comment);
+            }
+            for alias in &module.aliases {
+                add(
+                    Some(&alias.name),
+                    "alias",
+                    Some(alias.type_annotation.clone()),
+                    &alias.comment,
+                );
+            }
+            for value in &module.values {
+                add(
+                    Some(&value.name),
+                    "value",
+                    Some(value.type_annotation.clone()),
+                    &value.comment,
+                );
+            }
+            for binop in &module.binops {
+                add(
+                    Some(&binop.name),
+                    "binop",
+                    Some(binop.type_annotation.clone()),
+                    &binop.comment,
+                );
+            }
+        }
+    }
+
+    fn add(&mut self, document: Document) {
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let name_terms = match &document.name {
+            Some(name) => tokenize(name),
+            None => tokenize(&document.module),
+        };
+        for term in name_terms {
+            *frequencies.entry(term).or_default() += NAME_WEIGHT;
+        }
+        for term in tokenize(&document.comment) {
+            *frequencies.entry(term).or_default() += 1;
+        }
+
+        let id = self.documents.len();
+        let length: u32 = frequencies.values().sum();
+        for (term, frequency) in frequencies {
+            self.postings.entry(term).or_default().push((id, frequency));
+        }
+        self.lengths.push(length);
+        self.total_length += length as u64;
+        self.documents.push(document);
+    }
+
+    /// The `limit` best matches for `query`, highest score first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<DocHit> {
+        if self.is_empty() {
+            return vec![];
+        }
+
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let count = self.documents.len() as f64;
+        let average_length = self.total_length as f64 / count;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let matching = postings.len() as f64;
+            let idf = ((count - matching + 0.5) / (matching + 0.5) + 1.0).ln();
+            for &(id, frequency) in postings {
+                let frequency = frequency as f64;
+                let length = self.lengths[id] as f64;
+                let score = idf * frequency * (K1 + 1.0)
+                    / (frequency + K1 * (1.0 - B + B * length / average_length));
+                *scores.entry(id).or_default() += score;
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(id, score)| {
+                let document = &self.documents[id];
+                DocHit {
+                    package: document.package.clone(),
+                    version: document.version.clone(),
+                    module: document.module.clone(),
+                    name: document.name.clone(),
+                    kind: document.kind,
+                    type_annotation: document.type_annotation.clone(),
+                    score: (score * 1000.0).round() / 1000.0,
+                    snippet: snippet(&document.comment, &terms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split text into lowercase, lightly stemmed terms. camelCase and dotted
+/// names are split into their words, so `percentEncode` matches "percent encode".
+fn tokenize(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if c.is_uppercase() && previous_lower && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = c.is_lowercase() || c.is_numeric();
+            current.extend(c.to_lowercase());
+        } else {
+            previous_lower = false;
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+        .into_iter()
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+        .collect()
+}
+
+/// Strip common English suffixes so "decoding", "decoder" and "decodes" meet
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ers", "er", "ed", "es", "s", "e"] {
+        if let Some(stripped) = word.strip_suffix(suffix) {
+            if stripped.len() >= 3 {
+                return stripped.to_string();
+            }
+        }
+    }
+    word.to_string()
+}
+
+/// The part of `comment` around the first query term, on one line
+fn snippet(comment: &str, terms: &[String]) -> String {
+    let flattened = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = flattened.to_lowercase();
+    let first_match = terms
+        .iter()
+        .filter_map(|term| lower.find(term.as_str()))
+        .min()
+        .unwrap_or(0);
+
+    // Start a little before the match, on a character and word boundary
+    let mut start = first_match.saturating_sub(SNIPPET_LENGTH / 4);
+    while !flattened.is_char_boundary(start) {
+        start -= 1;
+    }
+    if start > 0 {
+        start = flattened[..start].rfind(' ').map(|i| i + 1).unwrap_or(0);
+    }
+
+    let text: String = flattened[start..].chars().take(SNIPPET_LENGTH).collect();
+    let truncated = start + text.len() < flattened.len();
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        text,
+        if truncated { "…" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::fetcher::parse_docs;
+
+    fn package(name: &str) -> PackageInfo {
+        PackageInfo {
+            author: "test".to_string(),
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    fn value(name: &str, comment: &str) -> String {
+        format!(r#"{{"name": "{name}", "comment": "{comment}", "type": "a -> a"}}"#)
+    }
+
+    fn docs(module: &str, values: &[String]) -> Vec<Module> {
+        parse_docs(&format!(
+            r#"[{{"name": "{module}", "comment": "", "unions": [], "aliases": [], "values": [{}], "binops": []}}]"#,
+            values.join(",")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("percentEncode"), vec!["percent", "encod"]);
+        assert_eq!(tokenize("percent-encoding"), vec!["percent", "encod"]);
+        assert_eq!(tokenize("Url.Builder"), vec!["url", "build"]);
+        assert_eq!(
+            tokenize("Decoding the JSON values"),
+            vec!["decod", "json", "valu"]
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_names_and_rare_terms() {
+        let mut index = DocIndex::new();
+        index.add_package(
+            &package("url"),
+            &docs(
+                "Url",
+                &[
+                    value(
+                        "percentEncode",
+                        "Use percent-encoding to make a string safe for URLs.",
+                    ),
+                    value("toString", "Turn a URL into a string."),
+                ],
+            ),
+        );
+        index.add_package(
+            &package("time"),
+            &docs(
+                "Debounce",
+                &[
+                    value("debounce", "Wait until events stop arriving before acting."),
+                    value(
+                        "throttle",
+                        "Act at most once per interval, unlike debounce.",
+                    ),
+                ],
+            ),
+        );
+        assert_eq!(index.len(), 4);
+
+        let hits = index.search("percent encode", 5);
+        assert_eq!(hits[0].name.as_deref(), Some("percentEncode"));
+        assert!(hits[0].snippet.contains("percent-encoding"));
+
+        let hits = index.search("debounce", 5);
+        assert_eq!(hits[0].name.as_deref(), Some("debounce"));
+        assert_eq!(hits[0].module, "Debounce");
+        assert!(hits.iter().any(|h| h.name.as_deref() == Some("throttle")));
+
+        assert!(index.search("nonexistent", 5).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_windows_long_comments() {
+        let comment = format!(
+            "{} the needle is here {}",
+            "word ".repeat(100),
+            "tail ".repeat(100)
+        );
+        let text = snippet(&comment, &["needl".to_string()]);
+        assert!(text.starts_with('…'));
+        assert!(text.ends_with('…'));
+        assert!(text.contains("needle"));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::elm::fetcher::Module;
+use crate::elm::PackageInfo;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Where a cached value was loaded from. Local files are reloaded when their
+/// modification time changes; published registry files never change.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Local {
+        path: PathBuf,
+        modified: Option<SystemTime>,
+    },
+    Registry,
+}
+
+impl Origin {
+    pub fn local(path: PathBuf) -> Self {
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        Origin::Local { path, modified }
+    }
+}
+
+struct Entry<T> {
+    origin: Origin,
+    value: Arc<T>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StoreStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Misses caused by an entry whose file changed since it was cached
+    pub invalidations: u64,
+}
+
+struct Store<T> {
+    entries: HashMap<PackageInfo, Entry<T>>,
+    stats: StoreStats,
+}
+
+impl<T> Default for Store<T> {
+    fn default() -> Self {
+        Store {
+            entries: HashMap::new(),
+            stats: StoreStats::default(),
+        }
+    }
+}
+
+impl<T> Store<T> {
+    fn lookup(&mut self, package: &PackageInfo, origin: &Origin) -> Option<Arc<T>> {
+        match self.entries.get(package) {
+            Some(entry) if &entry.origin == origin => {
+                self.stats.hits += 1;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                self.stats.misses += 1;
+                self.stats.invalidations += 1;
+                None
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, package: &PackageInfo, origin: Origin, value: Arc<T>) {
+        self.entries
+            .insert(package.clone(), Entry { origin, value });
+        self.stats.entries = self.entries.len();
+    }
+}
+
+#[derive(Default)]
+struct DocsCache {
+    docs: Store<Vec<Module>>,
+    readmes: Store<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub docs: StoreStats,
+    pub readmes: StoreStats,
+}
+
+fn cache() -> &'static Mutex<DocsCache> {
+    static CACHE: OnceLock<Mutex<DocsCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(DocsCache::default()))
+}
+
+/// Look up a value in `store`, calling `load` on a miss. The lock is not
+/// held while loading, so a slow registry fetch doesn't block other lookups.
+fn get_or_load<T>(
+    store: impl Fn(&mut DocsCache) -> &mut Store<T>,
+    package: &PackageInfo,
+    origin: Origin,
+    load: impl FnOnce() -> Result<T, String>,
+) -> Result<Arc<T>, String> {
+    if let Some(value) = store(&mut cache().lock().unwrap()).lookup(package, &origin) {
+        return Ok(value);
+    }
+
+    let value = Arc::new(load()?);
+    store(&mut cache().lock().unwrap()).insert(package, origin, value.clone());
+    Ok(value)
+}
+
+/// Parsed docs.json for `package`, loaded with `load` unless a cached copy
+/// from the same `origin` is available
+pub fn docs(
+    package: &PackageInfo,
+    origin: Origin,
+    load: impl FnOnce() -> Result<Vec<Module>, String>,
+) -> Result<Arc<Vec<Module>>, String> {
+    get_or_load(|cache| &mut cache.docs, package, origin, load)
+}
+
+/// README for `package`, loaded with `load` unless a cached copy from the
+/// same `origin` is available
+pub fn readme(
+    package: &PackageInfo,
+    origin: Origin,
+    load: impl FnOnce() -> Result<String, String>,
+) -> Result<Arc<String>, String> {
+    get_or_load(|cache| &mut cache.readmes, package, origin, load)
+}
+
+pub fn stats() -> CacheStats {
+    let cache = cache().lock().unwrap();
+    CacheStats {
+        docs: cache.docs.stats.clone(),
+        readmes: cache.readmes.stats.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::test_dir::TestDir;
+    use std::fs::{self, File};
+    use std::time::Duration;
+
+    #[test]
+    fn test_store_invalidates_on_mtime_change() {
+        let dir = TestDir::new("docs-cache");
+        let path = dir.join("README.md");
+        fs::write(&path, "v1").unwrap();
+        let package = PackageInfo {
+            author: "elm".to_string(),
+            name: "core".to_string(),
+            version: "1.0.5".to_string(),
+        };
+        let mut store: Store<String> = Store::default();
+
+        let origin = Origin::local(path.clone());
+        assert!(store.lookup(&package, &origin).is_none());
+        store.insert(&package, origin, Arc::new("v1".to_string()));
+        assert_eq!(
+            store
+                .lookup(&package, &Origin::local(path.clone()))
+                .as_deref(),
+            Some(&"v1".to_string())
+        );
+
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(store
+            .lookup(&package, &Origin::local(path.clone()))
+            .is_none());
+
+        assert_eq!(store.stats.hits, 1);
+        assert_eq!(store.stats.misses, 2);
+        assert_eq!(store.stats.invalidations, 1);
+        assert_eq!(store.stats.entries, 1);
+    }
+}
@@ -0,0 +1,551 @@
+use crate::elm::cache;
+use crate::elm::doc_search::DocIndex;
+use crate::elm::fetcher::{self, Module};
+use crate::elm::search::SearchEntry;
+use crate::elm::{registry, PackageInfo};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Downloads between manifest saves, so an interrupted sync keeps most of
+/// its progress
+const SAVE_EVERY: usize = 50;
+
+/// Downloads per `search_registry_docs` call unless it asks for another
+/// limit, so the first sync of the whole registry is spread over several
+/// calls instead of blocking one for minutes
+pub const DEFAULT_MAX_DOWNLOADS: usize = 200;
+
+/// Transient download errors in a row after which a sync gives up, so an
+/// unreachable registry doesn't cost a timeout per package
+const MAX_CONSECUTIVE_ERRORS: usize = 5;
+
+/// What the mirror holds: the version mirrored for each package, and the
+/// versions the registry has no usable docs.json for, which aren't retried
+/// until a newer version is published
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub packages: BTreeMap<String, String>,
+    #[serde(default)]
+    pub failed: BTreeMap<String, String>,
+    /// Unix timestamp of the last sync
+    pub synced_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncFailure {
+    pub package: String,
+    pub version: String,
+    pub reason: String,
+    /// Whether the next sync tries again. Network errors and server errors
+    /// are retried; a missing or unparseable docs.json isn't.
+    pub retry: bool,
+}
+
+/// Why a docs.json couldn't be mirrored
+enum DownloadError {
+    /// The registry has no usable docs.json for this version
+    Definitive(String),
+    /// A network or server error that may go away
+    Transient(String),
+}
+
+/// The package version an entry of the registry's search index names,
+/// checked before its name and version are used in mirror paths
+fn entry_package(entry: &SearchEntry) -> Result<PackageInfo, String> {
+    let (author, name) = entry
+        .name
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid package name '{}'", entry.name))?;
+    let package = PackageInfo {
+        author: author.to_string(),
+        name: name.to_string(),
+        version: entry.version.clone(),
+    };
+    fetcher::validate_package(&package)?;
+    Ok(package)
+}
+
+/// Download and check the docs.json of `package` from the registry at
+/// `base_url`
+fn download_docs(base_url: &str, package: &PackageInfo) -> Result<String, DownloadError> {
+    let url = registry::package_file_url(base_url, package, "docs.json");
+    let response = registry::client()
+        .map_err(DownloadError::Transient)?
+        .get(&url)
+        .send()
+        .map_err(|e| DownloadError::Transient(format!("Failed to fetch {url}: {e}")))?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+        return Err(DownloadError::Definitive(format!(
+            "Failed to fetch {url}: HTTP {status}"
+        )));
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Transient(format!(
+            "Failed to fetch {url}: HTTP {status}"
+        )));
+    }
+
+    let content = response.text().map_err(|e| {
+        DownloadError::Transient(format!("Failed to read response from {url}: {e}"))
+    })?;
+    fetcher::parse_docs(&content).map_err(DownloadError::Definitive)?;
+    Ok(content)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// Packages mirrored for the first time
+    pub added: Vec<String>,
+    /// Packages whose mirrored docs were replaced by a newer version
+    pub updated: Vec<String>,
+    pub failed: Vec<SyncFailure>,
+    /// Packages not tried because `max_downloads` was reached or the sync
+    /// gave up after repeated network errors
+    pub remaining: usize,
+}
+
+/// How much of the registry the mirror covers
+#[derive(Debug, Clone, Serialize)]
+pub struct Coverage {
+    /// Packages with docs in the mirror
+    pub mirrored: usize,
+    /// Packages in the registry's search index, if it could be read
+    pub published: Option<usize>,
+    /// Published packages missing from the mirror or mirrored at an older
+    /// version, not counting ones whose docs couldn't be downloaded
+    pub remaining: Option<usize>,
+    /// Versions whose docs couldn't be downloaded
+    pub failed: usize,
+}
+
+/// A local copy of the docs.json of the latest version of every published
+/// package, laid out like the registry:
+///
+/// ```text
+/// <dir>/manifest.json
+/// <dir>/packages/<author>/<name>/<version>/docs.json
+/// ```
+///
+/// Syncing only downloads packages that are new or have a newer version than
+/// the mirrored one, and searching never touches the network.
+pub struct DocsMirror {
+    dir: PathBuf,
+}
+
+impl DocsMirror {
+    pub fn new(dir: PathBuf) -> Self {
+        DocsMirror { dir }
+    }
+
+    /// The mirror in `docs-mirror` under the cache directory
+    pub fn from_env() -> Result<Self, String> {
+        Ok(DocsMirror::new(cache::cache_dir()?.join("docs-mirror")))
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join("manifest.json")
+    }
+
+    fn docs_path(&self, package: &str, version: &str) -> PathBuf {
+        self.dir
+            .join("packages")
+            .join(package)
+            .join(version)
+            .join("docs.json")
+    }
+
+    /// The manifest, or an empty one if nothing has been mirrored yet
+    pub fn manifest(&self) -> Result<Manifest, String> {
+        let path = self.manifest_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(format!("Failed to read {}: {e}", path.display())),
+        }
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<(), String> {
+        let path = self.manifest_path();
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&path, serde_json::to_string(manifest).unwrap_or_default()))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+
+    /// Entries that are missing from the mirror or newer than the mirrored
+    /// version, skipping versions that failed before
+    fn pending<'a>(manifest: &Manifest, entries: &'a [SearchEntry]) -> Vec<&'a SearchEntry> {
+        entries
+            .iter()
+            .filter(|entry| {
+                manifest.packages.get(&entry.name) != Some(&entry.version)
+                    && manifest.failed.get(&entry.name) != Some(&entry.version)
+            })
+            .collect()
+    }
+
+    /// How much of `entries` (the registry's search index, if available) the
+    /// mirror covers
+    pub fn coverage(&self, entries: Option<&[SearchEntry]>) -> Result<Coverage, String> {
+        let manifest = self.manifest()?;
+        Ok(Coverage {
+            mirrored: manifest.packages.len(),
+            published: entries.map(|entries| entries.len()),
+            remaining: entries.map(|entries| Self::pending(&manifest, entries).len()),
+            failed: manifest.failed.len(),
+        })
+    }
+
+    /// Bring the mirror up to date with `entries` (the registry's search
+    /// index, which lists each package's latest version), downloading every
+    /// new or updated package's docs.json from the registry at `base_url`, or
+    /// at most `max_downloads` of them
+    pub fn sync(
+        &self,
+        base_url: &str,
+        entries: &[SearchEntry],
+        max_downloads: Option<usize>,
+    ) -> Result<SyncReport, String> {
+        let mut manifest = self.manifest()?;
+        let mut report = SyncReport::default();
+
+        let pending = Self::pending(&manifest, entries);
+        let pending_count = pending.len();
+        let max_downloads = max_downloads.unwrap_or(pending_count);
+        report.remaining = pending_count.saturating_sub(max_downloads);
+        let mut consecutive_errors = 0;
+
+        for (i, entry) in pending.into_iter().take(max_downloads).enumerate() {
+            if i > 0 && i % SAVE_EVERY == 0 {
+                self.save_manifest(&manifest)?;
+            }
+            let downloaded = entry_package(entry)
+                .map_err(DownloadError::Definitive)
+                .and_then(|package| download_docs(base_url, &package));
+            let (reason, retry) = match downloaded {
+                Ok(content) => {
+                    consecutive_errors = 0;
+                    self.store(&mut manifest, &mut report, entry, content)?;
+                    continue;
+                }
+                Err(DownloadError::Definitive(reason)) => {
+                    manifest
+                        .failed
+                        .insert(entry.name.clone(), entry.version.clone());
+                    (reason, false)
+                }
+                Err(DownloadError::Transient(reason)) => {
+                    consecutive_errors += 1;
+                    (reason, true)
+                }
+            };
+            report.failed.push(SyncFailure {
+                package: entry.name.clone(),
+                version: entry.version.clone(),
+                reason,
+                retry,
+            });
+            if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                report.remaining = pending_count - (i + 1);
+                break;
+            }
+        }
+
+        manifest.synced_at = Some(chrono::Utc::now().timestamp());
+        self.save_manifest(&manifest)?;
+        Ok(report)
+    }
+
+    /// Write a downloaded docs.json into the mirror, replacing the docs of any
+    /// older mirrored version
+    fn store(
+        &self,
+        manifest: &mut Manifest,
+        report: &mut SyncReport,
+        entry: &SearchEntry,
+        content: String,
+    ) -> Result<(), String> {
+        let path = self.docs_path(&entry.name, &entry.version);
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))
+            .and_then(|_| fs::write(&path, content))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+
+        manifest.failed.remove(&entry.name);
+        match manifest
+            .packages
+            .insert(entry.name.clone(), entry.version.clone())
+        {
+            Some(previous) => {
+                let previous_dir = self.dir.join("packages").join(&entry.name).join(previous);
+                let _ = fs::remove_dir_all(previous_dir);
+                report.updated.push(entry.name.clone());
+            }
+            None => report.added.push(entry.name.clone()),
+        }
+        Ok(())
+    }
+
+    /// Add the mirrored docs of `packages` (`author/name` to version) to
+    /// `index`. Packages whose docs.json went missing or can't be parsed are
+    /// left out.
+    fn add_packages<'a>(
+        &self,
+        index: &mut DocIndex,
+        packages: impl Iterator<Item = (&'a String, &'a String)>,
+    ) {
+        for (package, version) in packages {
+            let Some((author, name)) = package.split_once('/') else {
+                continue;
+            };
+            let Ok(content) = fs::read_to_string(self.docs_path(package, version)) else {
+                continue;
+            };
+            let Ok(modules) = serde_json::from_str::<Vec<Module>>(&content) else {
+                continue;
+            };
+            let package = PackageInfo {
+                author: author.to_string(),
+                name: name.to_string(),
+                version: version.clone(),
+            };
+            index.add_package(&package, &modules);
+        }
+    }
+
+    /// The search index over every mirrored package, kept for the life of the
+    /// process. After a sync that only added packages, the new ones are added
+    /// to a copy of the previous index; an update or removal rebuilds it.
+    pub fn index(&self) -> Result<Arc<DocIndex>, String> {
+        static INDEX: OnceLock<Mutex<Option<CachedIndex>>> = OnceLock::new();
+        let manifest = self.manifest()?;
+        let cache = INDEX.get_or_init(|| Mutex::new(None));
+
+        let previous = cache
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|cached| cached.dir == self.dir)
+            .map(|cached| (cached.packages.clone(), cached.index.clone()));
+        let index = match previous {
+            Some((packages, index)) if packages == manifest.packages => return Ok(index),
+            Some((packages, index))
+                if packages
+                    .iter()
+                    .all(|(package, version)| manifest.packages.get(package) == Some(version)) =>
+            {
+                let mut index = (*index).clone();
+                self.add_packages(
+                    &mut index,
+                    manifest
+                        .packages
+                        .iter()
+                        .filter(|(package, _)| !packages.contains_key(*package)),
+                );
+                index
+            }
+            _ => {
+                let mut index = DocIndex::new();
+                self.add_packages(&mut index, manifest.packages.iter());
+                index
+            }
+        };
+
+        let index = Arc::new(index);
+        *cache.lock().unwrap() = Some(CachedIndex {
+            dir: self.dir.clone(),
+            packages: manifest.packages,
+            index: index.clone(),
+        });
+        Ok(index)
+    }
+}
+
+/// The index last built by [`DocsMirror::index`], and the packages in it
+struct CachedIndex {
+    dir: PathBuf,
+    packages: BTreeMap<String, String>,
+    index: Arc<DocIndex>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::registry::tests::{serve, serve_with};
+    use crate::elm::test_dir::TestDir;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn entry(name: &str, version: &str) -> SearchEntry {
+        SearchEntry {
+            name: name.to_string(),
+            summary: String::new(),
+            license: "BSD-3-Clause".to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn docs(module: &str, value: &str, comment: &str) -> String {
+        format!(
+            r#"[{{"name": "{module}", "comment": "", "unions": [], "aliases": [], "values": [{{"name": "{value}", "comment": "{comment}", "type": "String -> Maybe.Maybe Time.Posix"}}], "binops": []}}]"#
+        )
+    }
+
+    #[test]
+    fn test_sync_is_incremental_and_searchable_offline() {
+        let base_url = serve(HashMap::from([
+            (
+                "/packages/rtfeldman/elm-iso8601-date-strings/1.1.4/docs.json".to_string(),
+                docs(
+                    "Iso8601",
+                    "toTime",
+                    "Parse an ISO-8601 formatted date string.",
+                ),
+            ),
+            (
+                "/packages/elm/time/1.0.0/docs.json".to_string(),
+                docs("Time", "millisToPosix", "Turn milliseconds into a time."),
+            ),
+            (
+                "/packages/elm/time/1.0.1/docs.json".to_string(),
+                docs("Time", "millisToPosix", "Turn milliseconds into a time."),
+            ),
+        ]));
+        let dir = TestDir::new("docs-mirror");
+        let mirror = DocsMirror::new(dir.to_path_buf());
+
+        let entries = vec![
+            entry("rtfeldman/elm-iso8601-date-strings", "1.1.4"),
+            entry("elm/time", "1.0.0"),
+            entry("nobody/no-docs", "1.0.0"),
+        ];
+        let first = mirror.sync(&base_url, &entries, Some(1)).unwrap();
+        assert_eq!(first.added, vec!["rtfeldman/elm-iso8601-date-strings"]);
+        assert_eq!(first.remaining, 2);
+        let coverage = mirror.coverage(Some(&entries)).unwrap();
+        assert_eq!(
+            (coverage.mirrored, coverage.published, coverage.remaining),
+            (1, Some(3), Some(2))
+        );
+
+        // Searching reads only the mirror
+        let index = mirror.index().unwrap();
+        let hits = index.search("parse iso 8601 date", 5);
+        assert_eq!(hits[0].package, "rtfeldman/elm-iso8601-date-strings");
+        assert_eq!(hits[0].name.as_deref(), Some("toTime"));
+        assert!(index.search("milliseconds", 5).is_empty());
+
+        // Only what's missing is downloaded, and the index is extended
+        let second = mirror.sync(&base_url, &entries, None).unwrap();
+        assert_eq!(second.added, vec!["elm/time"]);
+        assert_eq!(second.failed[0].package, "nobody/no-docs");
+        assert_eq!(second.remaining, 0);
+        let coverage = mirror.coverage(Some(&entries)).unwrap();
+        assert_eq!((coverage.mirrored, coverage.remaining), (2, Some(0)));
+        let index = mirror.index().unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("milliseconds", 5)[0].version, "1.0.0");
+
+        // Failed versions aren't retried, and only the newer version is fetched
+        let entries = vec![
+            entry("rtfeldman/elm-iso8601-date-strings", "1.1.4"),
+            entry("elm/time", "1.0.1"),
+            entry("nobody/no-docs", "1.0.0"),
+        ];
+        let third = mirror.sync(&base_url, &entries, None).unwrap();
+        assert_eq!(third.updated, vec!["elm/time"]);
+        assert!(third.added.is_empty() && third.failed.is_empty());
+        assert!(!dir.join("packages/elm/time/1.0.0").exists());
+
+        let index = DocsMirror::new(dir.to_path_buf()).index().unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("milliseconds", 5)[0].version, "1.0.1");
+    }
+
+    #[test]
+    fn test_only_definitive_failures_are_remembered() {
+        // The registry errors on the first request for elm/time, and always
+        // for anything by `down`
+        let failed_once = Arc::new(AtomicBool::new(false));
+        let base_url = serve_with(move |request| match request.path.as_str() {
+            "/packages/elm/time/1.0.0/docs.json" if !failed_once.swap(true, Ordering::SeqCst) => {
+                (503, vec![], "busy".to_string())
+            }
+            "/packages/elm/time/1.0.0/docs.json" => (200, vec![], docs("Time", "now", "")),
+            "/packages/bad/docs/1.0.0/docs.json" => (200, vec![], "not json".to_string()),
+            path if path.starts_with("/packages/down/") => (500, vec![], String::new()),
+            _ => (404, vec![], String::new()),
+        });
+        let dir = TestDir::new("docs-mirror-failures");
+        let mirror = DocsMirror::new(dir.to_path_buf());
+
+        let entries = vec![
+            entry("elm/time", "1.0.0"),
+            entry("bad/docs", "1.0.0"),
+            entry("nobody/no-docs", "1.0.0"),
+        ];
+        let first = mirror.sync(&base_url, &entries, None).unwrap();
+        let retries: Vec<(&str, bool)> = first
+            .failed
+            .iter()
+            .map(|f| (f.package.as_str(), f.retry))
+            .collect();
+        assert_eq!(
+            retries,
+            vec![
+                ("elm/time", true),
+                ("bad/docs", false),
+                ("nobody/no-docs", false)
+            ]
+        );
+        assert_eq!(mirror.coverage(Some(&entries)).unwrap().remaining, Some(1));
+
+        let second = mirror.sync(&base_url, &entries, None).unwrap();
+        assert_eq!(second.added, vec!["elm/time"]);
+        assert!(second.failed.is_empty());
+
+        // A registry that keeps failing is given up on, leaving the rest
+        let entries: Vec<SearchEntry> = (0..8)
+            .map(|i| entry(&format!("down/package-{i}"), "1.0.0"))
+            .collect();
+        let report = mirror.sync(&base_url, &entries, None).unwrap();
+        assert_eq!(report.failed.len(), MAX_CONSECUTIVE_ERRORS);
+        assert_eq!(report.remaining, 8 - MAX_CONSECUTIVE_ERRORS);
+        assert_eq!(mirror.coverage(Some(&entries)).unwrap().remaining, Some(8));
+    }
+
+    #[test]
+    fn test_invalid_entries_are_never_downloaded() {
+        let requested = Arc::new(AtomicBool::new(false));
+        let seen = requested.clone();
+        let base_url = serve_with(move |_| {
+            seen.store(true, Ordering::SeqCst);
+            (200, vec![], docs("Evil", "evil", ""))
+        });
+        let dir = TestDir::new("docs-mirror-invalid");
+        let mirror = DocsMirror::new(dir.join("mirror"));
+
+        let entries = vec![
+            entry("../evil", "1.0.0"),
+            entry("elm/..", "1.0.0"),
+            entry("elm/time", "../../../evil"),
+            entry("no-author", "1.0.0"),
+        ];
+        let report = mirror.sync(&base_url, &entries, None).unwrap();
+        assert!(!requested.load(Ordering::SeqCst));
+        assert!(report.added.is_empty());
+        assert_eq!(report.failed.len(), 4);
+        assert!(report.failed.iter().all(|failure| !failure.retry));
+        assert_eq!(mirror.coverage(Some(&entries)).unwrap().remaining, Some(0));
+        assert!(!dir.join("evil").exists());
+    }
+}
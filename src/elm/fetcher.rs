@@ -1,8 +1,12 @@
-use crate::elm::PackageInfo;
+use crate::elm::annotation::{self, Type};
+use crate::elm::docs_cache::{self, Origin};
+use crate::elm::version::Version;
+use crate::elm::{home, registry, PackageInfo};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 // Custom deserializer for comment fields that might be either a string or an array
 fn deserialize_comment<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -36,6 +40,8 @@ pub struct Alias {
     pub args: Vec<String>,
     #[serde(rename = "type")]
     pub type_annotation: String,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub parsed_type: Option<Type>,
 }
 
 // Custom deserializer for union cases which are [name, [types...]]
@@ -95,6 +101,15 @@ pub struct Union {
     pub args: Vec<String>,
     #[serde(deserialize_with = "deserialize_cases")]
     pub cases: Vec<Vec<String>>,
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub parsed_cases: Vec<Case>,
+}
+
+/// A union constructor with its argument types parsed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Case {
+    pub name: String,
+    pub args: Vec<Type>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +119,8 @@ pub struct Value {
     pub type_annotation: String,
     #[serde(deserialize_with = "deserialize_comment")]
     pub comment: String,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub parsed_type: Option<Type>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,61 +132,308 @@ pub struct Binop {
     pub comment: String,
     pub associativity: String,
     pub precedence: i32,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub parsed_type: Option<Type>,
 }
 
-pub fn fetch_readme(package: &PackageInfo) -> Result<String, String> {
-    let package_path = get_package_path(package)?;
-    let readme_path = package_path.join("README.md");
+impl Module {
+    /// Parse every raw type annotation in the module into a [`Type`].
+    ///
+    /// Annotations that fail to parse are left as `None` (or the case is
+    /// omitted from `parsed_cases`); the raw text is always kept.
+    fn parse_types(&mut self) {
+        for alias in &mut self.aliases {
+            alias.parsed_type = annotation::parse(&alias.type_annotation).ok();
+        }
+        for value in &mut self.values {
+            value.parsed_type = annotation::parse(&value.type_annotation).ok();
+        }
+        for binop in &mut self.binops {
+            binop.parsed_type = annotation::parse(&binop.type_annotation).ok();
+        }
+        for union in &mut self.unions {
+            union.parsed_cases = union
+                .cases
+                .iter()
+                .filter_map(|case| {
+                    let (name, args) = case.split_first()?;
+                    let args = args
+                        .iter()
+                        .map(|arg| annotation::parse(arg))
+                        .collect::<Result<Vec<_>, _>>()
+                        .ok()?;
+                    Some(Case {
+                        name: name.clone(),
+                        args,
+                    })
+                })
+                .collect();
+        }
+    }
+}
 
-    if !readme_path.exists() {
-        return Err(format!(
-            "README.md not found for package {}/{} version {}. Make sure the package is installed locally.",
-            package.author, package.name, package.version
-        ));
+/// Parse the contents of a docs.json file, populating the structured types
+/// alongside the raw annotation strings
+pub fn parse_docs(content: &str) -> Result<Vec<Module>, String> {
+    let mut modules: Vec<Module> =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse docs JSON: {e}"))?;
+
+    for module in &mut modules {
+        module.parse_types();
     }
 
-    fs::read_to_string(&readme_path).map_err(|e| format!("Failed to read README.md: {e}"))
+    Ok(modules)
+}
+
+/// Fetch a package's README. `elm_version` is the project's `elm-version`,
+/// used to pick which compiler's package cache to read from.
+pub fn fetch_readme(
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<Arc<String>, String> {
+    fetch_readme_from(&registry::base_url(), package, elm_version)
+}
+
+/// [`fetch_readme`], falling back to the registry at `registry_url`
+pub fn fetch_readme_from(
+    registry_url: &str,
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<Arc<String>, String> {
+    let location = locate_package_file(package, "README.md", elm_version)?;
+    docs_cache::readme(package, location.origin(), || {
+        read_located_file(package, "README.md", &location, registry_url)
+    })
+}
+
+/// Fetch and parse a package's docs.json. `elm_version` is the project's
+/// `elm-version`, used to pick which compiler's package cache to read from.
+///
+/// Parsed docs are cached for the life of the process and reloaded when the
+/// local docs.json changes.
+pub fn fetch_docs(
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<Arc<Vec<Module>>, String> {
+    fetch_docs_from(&registry::base_url(), package, elm_version)
 }
 
-pub fn fetch_docs(package: &PackageInfo) -> Result<Vec<Module>, String> {
-    let package_path = get_package_path(package)?;
-    let docs_path = package_path.join("docs.json");
+/// [`fetch_docs`], falling back to the registry at `registry_url`
+pub fn fetch_docs_from(
+    registry_url: &str,
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<Arc<Vec<Module>>, String> {
+    let location = locate_package_file(package, "docs.json", elm_version)?;
+    docs_cache::docs(package, location.origin(), || {
+        let docs_content = read_located_file(package, "docs.json", &location, registry_url)?;
+        parse_docs(&docs_content)
+    })
+}
 
-    if !docs_path.exists() {
-        return Err(format!(
-            "docs.json not found for package {}/{} version {}. Make sure the package is installed locally.",
+/// Fetch a package's own elm.json, which lists its dependency constraints.
+/// `elm_version` is the project's `elm-version`, as for [`fetch_docs`].
+pub fn fetch_elm_json(
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<JsonValue, String> {
+    let location = locate_package_file(package, "elm.json", elm_version)?;
+    let content = read_located_file(package, "elm.json", &location, &registry::base_url())?;
+    serde_json::from_str(&content).map_err(|e| {
+        format!(
+            "Failed to parse elm.json of {}/{} {}: {e}",
             package.author, package.name, package.version
-        ));
+        )
+    })
+}
+
+/// Where a package file will be read from
+enum Location {
+    Local(PathBuf),
+    /// Not installed locally; `local_error` explains where we looked
+    Registry {
+        local_error: String,
+    },
+}
+
+impl Location {
+    fn origin(&self) -> Origin {
+        match self {
+            Location::Local(path) => Origin::local(path.clone()),
+            Location::Registry { .. } => Origin::Registry,
+        }
     }
+}
 
-    let docs_content =
-        fs::read_to_string(&docs_path).map_err(|e| format!("Failed to read docs.json: {e}"))?;
+/// Check that `package` names a real package version before it's used in a
+/// cache path or registry URL: the author and name in the registry's
+/// character set, and a `major.minor.patch` version
+pub fn validate_package(package: &PackageInfo) -> Result<(), String> {
+    registry::validate_package_name(&package.author, &package.name)?;
+    match Version::parse(&package.version) {
+        Ok(version) if version.to_string() == package.version => Ok(()),
+        _ => Err(format!(
+            "Invalid version '{}' of {}/{}, expected 'major.minor.patch'",
+            package.version, package.author, package.name
+        )),
+    }
+}
 
-    let modules: Vec<Module> = serde_json::from_str(&docs_content)
-        .map_err(|e| format!("Failed to parse docs JSON: {e}"))?;
+fn locate_package_file(
+    package: &PackageInfo,
+    file: &str,
+    elm_version: Option<&str>,
+) -> Result<Location, String> {
+    validate_package(package)?;
+    Ok(match home::find_package_dir(package, elm_version) {
+        Ok(package_path) => {
+            let file_path = package_path.join(file);
+            if file_path.exists() {
+                return Ok(Location::Local(file_path));
+            }
+            Location::Registry {
+                local_error: format!(
+                    "{} not found for package {}/{} version {} at {}.",
+                    file,
+                    package.author,
+                    package.name,
+                    package.version,
+                    package_path.display()
+                ),
+            }
+        }
+        Err(local_error) => Location::Registry { local_error },
+    })
+}
 
-    Ok(modules)
+fn read_located_file(
+    package: &PackageInfo,
+    file: &str,
+    location: &Location,
+    registry_url: &str,
+) -> Result<String, String> {
+    match location {
+        Location::Local(path) => {
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {file}: {e}"))
+        }
+        Location::Registry { local_error } => {
+            registry::fetch_package_file(registry_url, package, file).map_err(|e| {
+                format!("{local_error} Falling back to the package registry also failed: {e}")
+            })
+        }
+    }
 }
 
-fn get_package_path(package: &PackageInfo) -> Result<PathBuf, String> {
-    let home_dir =
-        std::env::var("HOME").map_err(|_| "Could not determine HOME directory".to_string())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_validate_package() {
+        let package = |author: &str, name: &str, version: &str| PackageInfo {
+            author: author.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+        };
+        assert!(validate_package(&package("elm", "core", "1.0.5")).is_ok());
+        assert!(validate_package(&package("..", "core", "1.0.5")).is_err());
+        assert!(validate_package(&package("elm", "a/b", "1.0.5")).is_err());
+        assert!(validate_package(&package("elm", "core", "../1.0.5")).is_err());
+        assert!(validate_package(&package("elm", "core", "+1.0.5")).is_err());
+
+        let escaping = fetch_readme(&package("elm", "..", "1.0.0"), None).unwrap_err();
+        assert!(escaping.contains("Invalid package name"), "{escaping}");
+    }
+
+    #[test]
+    fn test_parse_docs_populates_types() {
+        let docs = r#"[{
+            "name": "Maybe",
+            "comment": "",
+            "unions": [{
+                "name": "Maybe",
+                "comment": "",
+                "args": ["a"],
+                "cases": [["Just", ["a"]], ["Nothing", []]]
+            }],
+            "aliases": [{
+                "name": "Pair",
+                "comment": [],
+                "args": ["a"],
+                "type": "{ first : a, second : a }"
+            }],
+            "values": [{
+                "name": "withDefault",
+                "comment": "",
+                "type": "a -> Maybe.Maybe a -> a"
+            }],
+            "binops": []
+        }]"#;
 
-    let package_path = PathBuf::from(home_dir)
-        .join(".elm")
-        .join("0.19.1")
-        .join("packages")
-        .join(&package.author)
-        .join(&package.name)
-        .join(&package.version);
+        let modules = parse_docs(docs).unwrap();
+        let module = &modules[0];
 
-    if !package_path.exists() {
-        return Err(format!(
-            "Package {}/{} version {} not found locally at {}. Make sure it's installed by running 'elm install' in an Elm project that uses this package.",
-            package.author, package.name, package.version,
-            package_path.display()
+        assert_eq!(
+            module.values[0].parsed_type,
+            Some(annotation::parse("a -> Maybe.Maybe a -> a").unwrap())
+        );
+        assert!(matches!(
+            module.aliases[0].parsed_type,
+            Some(Type::Record { .. })
         ));
+        let cases = &module.unions[0].parsed_cases;
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].name, "Just");
+        assert_eq!(
+            cases[0].args,
+            vec![Type::Var {
+                name: "a".to_string()
+            }]
+        );
+        assert!(cases[1].args.is_empty());
+    }
+
+    #[test]
+    fn test_read_package_file_falls_back_to_registry() {
+        let package = PackageInfo {
+            author: "not-installed-author".to_string(),
+            name: "not-installed".to_string(),
+            version: "1.0.0".to_string(),
+        };
+        let registry_url = registry::tests::serve(HashMap::from([(
+            "/packages/not-installed-author/not-installed/1.0.0/README.md".to_string(),
+            "# From the registry".to_string(),
+        )]));
+
+        let read = |file| {
+            let location = locate_package_file(&package, file, None)?;
+            read_located_file(&package, file, &location, &registry_url)
+        };
+        assert_eq!(read("README.md").unwrap(), "# From the registry");
+
+        let error = read("docs.json").unwrap_err();
+        assert!(error.contains("not found locally"));
+        assert!(error.contains("HTTP 404"));
     }
 
-    Ok(package_path)
+    #[test]
+    fn test_fetch_readme_falls_back_to_registry() {
+        let package = PackageInfo {
+            author: "not-installed-author".to_string(),
+            name: "readme-from-registry".to_string(),
+            version: "1.0.0".to_string(),
+        };
+        let registry_url = registry::tests::serve(HashMap::from([(
+            "/packages/not-installed-author/readme-from-registry/1.0.0/README.md".to_string(),
+            "# Through the docs cache".to_string(),
+        )]));
+
+        let readme = fetch_readme_from(&registry_url, &package, None).unwrap();
+        assert_eq!(readme.as_str(), "# Through the docs cache");
+
+        // The registry copy is cached for the rest of the process
+        let cached = fetch_readme(&package, None).unwrap();
+        assert_eq!(cached.as_str(), "# Through the docs cache");
+    }
 }
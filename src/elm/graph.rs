@@ -0,0 +1,531 @@
+use crate::elm::fetcher;
+use crate::elm::reader::{self, Dependency, Section};
+use crate::elm::version::{Constraint, Version};
+use crate::elm::PackageInfo;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Node {
+    /// `author/name`
+    pub package: String,
+    pub version: String,
+    /// Where the project's elm.json lists the package, if it does
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub section: Option<Section>,
+    /// Why the package's own dependencies couldn't be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    /// The constraint `from` puts on `to` in its elm.json
+    pub constraint: Constraint,
+    /// Whether the version of `to` in the graph satisfies `constraint`
+    pub satisfied: bool,
+}
+
+/// The package dependency graph of a project, from its roots down through
+/// each package's own elm.json
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyGraph {
+    pub roots: Vec<String>,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// One way a root depends on a package, as the chain of edges leading to it
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyPath {
+    /// The chain written out, e.g. `elm/browser -> elm/html -> elm/json`
+    pub summary: String,
+    pub edges: Vec<Edge>,
+}
+
+/// The ways the roots depend on a package, see [`DependencyGraph::paths_to`]
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyPaths {
+    pub paths: Vec<DependencyPath>,
+    /// Whether there are more than [`MAX_PATHS`] paths, and the longest were
+    /// left out
+    pub truncated: bool,
+}
+
+/// Stop enumerating paths after this many; wide graphs can have a lot
+pub const MAX_PATHS: usize = 100;
+
+fn full_name(package: &PackageInfo) -> String {
+    format!("{}/{}", package.author, package.name)
+}
+
+impl DependencyGraph {
+    /// Walk breadth-first from `roots`. `resolve` picks the version of each
+    /// dependency (normally the one pinned in the project's elm.json) and
+    /// `load` reads a package's own dependencies. `sections` maps `author/name`
+    /// to where the project lists it.
+    pub fn build(
+        roots: &[PackageInfo],
+        sections: &HashMap<String, Section>,
+        resolve: impl Fn(&Dependency) -> PackageInfo,
+        load: impl Fn(&PackageInfo) -> Result<Vec<Dependency>, String>,
+    ) -> DependencyGraph {
+        let mut graph = DependencyGraph {
+            roots: roots.iter().map(full_name).collect(),
+            nodes: vec![],
+            edges: vec![],
+        };
+        let mut seen: HashSet<String> = graph.roots.iter().cloned().collect();
+        let mut queue: VecDeque<PackageInfo> = roots.iter().cloned().collect();
+
+        while let Some(package) = queue.pop_front() {
+            let name = full_name(&package);
+            let mut node = Node {
+                package: name.clone(),
+                version: package.version.clone(),
+                section: sections.get(&name).copied(),
+                error: None,
+            };
+
+            match load(&package) {
+                Ok(dependencies) => {
+                    for dependency in dependencies {
+                        let target = resolve(&dependency);
+                        let target_name = full_name(&target);
+                        let satisfied = Version::parse(&target.version)
+                            .map(|v| dependency.constraint.contains(&v))
+                            .unwrap_or(false);
+                        graph.edges.push(Edge {
+                            from: name.clone(),
+                            to: target_name.clone(),
+                            constraint: dependency.constraint,
+                            satisfied,
+                        });
+                        if seen.insert(target_name) {
+                            queue.push_back(target);
+                        }
+                    }
+                }
+                Err(e) => node.error = Some(e),
+            }
+            graph.nodes.push(node);
+        }
+
+        graph
+    }
+
+    /// The graph of the project described by `elm_json`, rooted at its direct
+    /// dependencies (and direct test dependencies if `include_test`). Each
+    /// package's elm.json is read from the local Elm cache, falling back to
+    /// the registry.
+    pub fn for_project(elm_json: &Value, include_test: bool) -> DependencyGraph {
+        let elm_version = reader::get_elm_version(elm_json);
+        DependencyGraph::for_project_with(elm_json, include_test, |package| {
+            fetcher::fetch_elm_json(package, elm_version.as_deref())
+        })
+    }
+
+    /// Like `for_project`, with `load` reading each package's own elm.json
+    pub fn for_project_with(
+        elm_json: &Value,
+        include_test: bool,
+        load: impl Fn(&PackageInfo) -> Result<Value, String>,
+    ) -> DependencyGraph {
+        let elm_version = reader::get_elm_version(elm_json);
+
+        let mut sections: HashMap<String, Section> = HashMap::new();
+        let mut pinned: HashMap<String, String> = HashMap::new();
+        let mut roots: Vec<PackageInfo> = Vec::new();
+        for section in Section::ALL {
+            for dependency in reader::get_dependencies(elm_json, section) {
+                let package = dependency.resolve(elm_version.as_deref());
+                let name = full_name(&package);
+                if sections.contains_key(&name) {
+                    continue;
+                }
+                sections.insert(name.clone(), section);
+                if dependency.constraint.is_exact() {
+                    pinned.insert(name, package.version.clone());
+                }
+                let is_root = match section {
+                    Section::Direct => true,
+                    Section::TestDirect => include_test,
+                    _ => false,
+                };
+                if is_root {
+                    roots.push(package);
+                }
+            }
+        }
+
+        DependencyGraph::build(
+            &roots,
+            &sections,
+            |dependency| {
+                let name = format!("{}/{}", dependency.author, dependency.name);
+                match pinned.get(&name) {
+                    Some(version) => PackageInfo {
+                        author: dependency.author.clone(),
+                        name: dependency.name.clone(),
+                        version: version.clone(),
+                    },
+                    None => dependency.resolve(elm_version.as_deref()),
+                }
+            },
+            |package| Ok(reader::get_direct_dependencies(&load(package)?)),
+        )
+    }
+
+    pub fn node(&self, package: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.package == package)
+    }
+
+    /// Every path from a root to `package`, shortest first, up to
+    /// [`MAX_PATHS`]. A root that is itself `package` is not a path.
+    ///
+    /// The lengths of the paths from each node to `package` are worked out
+    /// first, so paths are enumerated one length at a time and never walk
+    /// into a branch that can't reach `package` at that length.
+    pub fn paths_to(&self, package: &str) -> DependencyPaths {
+        let mut outgoing: HashMap<&str, Vec<&Edge>> = HashMap::new();
+        for edge in &self.edges {
+            outgoing.entry(edge.from.as_str()).or_default().push(edge);
+        }
+
+        fn lengths_from<'a>(
+            from: &'a str,
+            target: &str,
+            outgoing: &HashMap<&str, Vec<&'a Edge>>,
+            lengths: &mut HashMap<&'a str, BTreeSet<usize>>,
+        ) {
+            if lengths.contains_key(from) {
+                return;
+            }
+            // Elm forbids cycles, but don't loop forever on a broken cache: a
+            // node still being visited counts as not reaching the target
+            lengths.insert(from, BTreeSet::new());
+            let mut found = BTreeSet::new();
+            for &edge in outgoing.get(from).into_iter().flatten() {
+                if edge.to == target {
+                    found.insert(1);
+                } else {
+                    lengths_from(&edge.to, target, outgoing, lengths);
+                    found.extend(lengths[edge.to.as_str()].iter().map(|l| l + 1));
+                }
+            }
+            lengths.insert(from, found);
+        }
+
+        fn walk<'a>(
+            from: &'a str,
+            target: &str,
+            remaining: usize,
+            outgoing: &HashMap<&str, Vec<&'a Edge>>,
+            lengths: &HashMap<&'a str, BTreeSet<usize>>,
+            current: &mut Vec<&'a Edge>,
+            found: &mut Vec<Vec<&'a Edge>>,
+        ) {
+            for &edge in outgoing.get(from).into_iter().flatten() {
+                if found.len() > MAX_PATHS {
+                    return;
+                }
+                let fits = if edge.to == target {
+                    remaining == 1
+                } else {
+                    lengths
+                        .get(edge.to.as_str())
+                        .is_some_and(|l| l.contains(&(remaining - 1)))
+                };
+                if !fits || edge.to == from || current.iter().any(|e| e.from == edge.to) {
+                    continue;
+                }
+                current.push(edge);
+                if edge.to == target {
+                    found.push(current.clone());
+                } else {
+                    walk(
+                        &edge.to,
+                        target,
+                        remaining - 1,
+                        outgoing,
+                        lengths,
+                        current,
+                        found,
+                    );
+                }
+                current.pop();
+            }
+        }
+
+        let roots: Vec<&str> = self
+            .roots
+            .iter()
+            .map(String::as_str)
+            .filter(|root| *root != package)
+            .collect();
+        let mut lengths: HashMap<&str, BTreeSet<usize>> = HashMap::new();
+        for root in &roots {
+            lengths_from(root, package, &outgoing, &mut lengths);
+        }
+        let path_lengths: BTreeSet<usize> = roots
+            .iter()
+            .flat_map(|root| lengths[root].iter().copied())
+            .collect();
+
+        // Look for one more than MAX_PATHS to know whether any were left out
+        let mut found: Vec<Vec<&Edge>> = Vec::new();
+        for length in path_lengths {
+            for root in &roots {
+                if lengths[root].contains(&length) {
+                    walk(
+                        root,
+                        package,
+                        length,
+                        &outgoing,
+                        &lengths,
+                        &mut Vec::new(),
+                        &mut found,
+                    );
+                }
+            }
+        }
+        let truncated = found.len() > MAX_PATHS;
+        found.truncate(MAX_PATHS);
+
+        let paths = found
+            .into_iter()
+            .map(|edges| {
+                let mut names = vec![edges[0].from.as_str()];
+                names.extend(edges.iter().map(|e| e.to.as_str()));
+                DependencyPath {
+                    summary: names.join(" -> "),
+                    edges: edges.into_iter().cloned().collect(),
+                }
+            })
+            .collect();
+        DependencyPaths { paths, truncated }
+    }
+
+    /// Graphviz DOT. Direct dependencies are bold; edges whose constraint
+    /// isn't satisfied are dashed red.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph dependencies {\n    rankdir=LR;\n");
+        for node in &self.nodes {
+            let style = if self.roots.contains(&node.package) {
+                ", style=bold"
+            } else {
+                ""
+            };
+            out.push_str(&format!(
+                "    \"{}\" [label=\"{}\\n{}\"{}];\n",
+                node.package, node.package, node.version, style
+            ));
+        }
+        for edge in &self.edges {
+            let style = if edge.satisfied {
+                ""
+            } else {
+                ", style=dashed, color=red"
+            };
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+                edge.from, edge.to, edge.constraint, style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart. Edges whose constraint isn't satisfied are dotted.
+    pub fn to_mermaid(&self) -> String {
+        let ids: HashMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.package.as_str(), format!("n{i}")))
+            .collect();
+
+        let mut out = String::from("graph LR\n");
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "    {}[\"{} {}\"]\n",
+                ids[node.package.as_str()],
+                node.package,
+                node.version
+            ));
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            let arrow = if edge.satisfied { "-->" } else { "-.->" };
+            // Mermaid reads `<` as the start of an HTML tag
+            let label = edge.constraint.to_string().replace('<', "#60;");
+            out.push_str(&format!("    {from} {arrow}|\"{label}\"| {to}\n"));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(full_name: &str, version: &str) -> PackageInfo {
+        let (author, name) = full_name.split_once('/').unwrap();
+        PackageInfo {
+            author: author.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn dependency(full_name: &str, constraint: &str) -> Dependency {
+        let (author, name) = full_name.split_once('/').unwrap();
+        Dependency {
+            author: author.to_string(),
+            name: name.to_string(),
+            constraint: Constraint::parse(constraint).unwrap(),
+        }
+    }
+
+    fn sample() -> DependencyGraph {
+        let pinned = HashMap::from([
+            ("elm/browser", "1.0.2"),
+            ("elm/core", "1.0.5"),
+            ("elm/json", "1.1.3"),
+        ]);
+        let sections = HashMap::from([
+            ("elm/browser".to_string(), Section::Direct),
+            ("elm/core".to_string(), Section::Direct),
+            ("elm/json".to_string(), Section::Indirect),
+        ]);
+        DependencyGraph::build(
+            &[
+                package("elm/browser", "1.0.2"),
+                package("elm/core", "1.0.5"),
+            ],
+            &sections,
+            |dep| {
+                let name = format!("{}/{}", dep.author, dep.name);
+                package(&name, pinned[name.as_str()])
+            },
+            |package| match package.name.as_str() {
+                "browser" => Ok(vec![
+                    dependency("elm/core", "1.0.0 <= v < 2.0.0"),
+                    dependency("elm/json", "1.0.0 <= v < 1.1.0"),
+                ]),
+                "json" => Ok(vec![dependency("elm/core", "1.0.0 <= v < 2.0.0")]),
+                "core" => Ok(vec![]),
+                _ => Err("not found".to_string()),
+            },
+        )
+    }
+
+    #[test]
+    fn test_build() {
+        let graph = sample();
+        let nodes: Vec<&str> = graph.nodes.iter().map(|n| n.package.as_str()).collect();
+        assert_eq!(nodes, vec!["elm/browser", "elm/core", "elm/json"]);
+        assert_eq!(graph.nodes[2].section, Some(Section::Indirect));
+        assert_eq!(graph.edges.len(), 3);
+
+        let json_edge = &graph.edges[1];
+        assert_eq!(json_edge.from, "elm/browser");
+        assert_eq!(json_edge.to, "elm/json");
+        assert!(!json_edge.satisfied);
+        assert!(graph.edges[0].satisfied);
+    }
+
+    fn summaries(paths: &DependencyPaths) -> Vec<&str> {
+        paths.paths.iter().map(|p| p.summary.as_str()).collect()
+    }
+
+    #[test]
+    fn test_paths_to() {
+        let graph = sample();
+
+        let paths = graph.paths_to("elm/json");
+        assert_eq!(summaries(&paths), vec!["elm/browser -> elm/json"]);
+        assert!(!paths.truncated);
+
+        let paths = graph.paths_to("elm/core");
+        assert_eq!(
+            summaries(&paths),
+            vec![
+                "elm/browser -> elm/core",
+                "elm/browser -> elm/json -> elm/core"
+            ]
+        );
+        assert_eq!(
+            paths.paths[1].edges[1].constraint.to_string(),
+            "1.0.0 <= v < 2.0.0"
+        );
+
+        assert!(graph.paths_to("elm/browser").paths.is_empty());
+    }
+
+    #[test]
+    fn test_paths_to_is_shortest_first_and_truncated() {
+        // Seven layers of two packages, each depending on both packages of
+        // the next layer, make 2^7 paths; the root also depends on the
+        // target directly, after everything else
+        let layer = |i: usize| [format!("t/layer{i}-a"), format!("t/layer{i}-b")];
+        let graph = DependencyGraph::build(
+            &[package("t/root", "1.0.0")],
+            &HashMap::new(),
+            |dep| package(&format!("{}/{}", dep.author, dep.name), "1.0.0"),
+            |package| {
+                let next = match package.name.strip_prefix("layer") {
+                    None if package.name == "root" => 0,
+                    None => return Ok(vec![]),
+                    Some(rest) => rest[..1].parse::<usize>().unwrap() + 1,
+                };
+                let mut dependencies: Vec<Dependency> = if next < 7 {
+                    layer(next)
+                        .iter()
+                        .map(|name| dependency(name, "1.0.0 <= v < 2.0.0"))
+                        .collect()
+                } else {
+                    vec![]
+                };
+                if next == 0 || next == 7 {
+                    dependencies.push(dependency("t/target", "1.0.0 <= v < 2.0.0"));
+                }
+                Ok(dependencies)
+            },
+        );
+
+        let paths = graph.paths_to("t/target");
+        assert!(paths.truncated);
+        assert_eq!(paths.paths.len(), MAX_PATHS);
+        assert_eq!(paths.paths[0].summary, "t/root -> t/target");
+        assert!(paths.paths[1..].iter().all(|p| p.edges.len() == 8));
+
+        let paths = graph.paths_to("t/layer6-a");
+        assert!(!paths.truncated);
+        assert_eq!(paths.paths.len(), 64);
+    }
+
+    #[test]
+    fn test_dot_and_mermaid() {
+        let graph = sample();
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph dependencies {"));
+        assert!(dot.contains("\"elm/browser\" [label=\"elm/browser\\n1.0.2\", style=bold];"));
+        assert!(dot.contains(
+            "\"elm/browser\" -> \"elm/json\" [label=\"1.0.0 <= v < 1.1.0\", style=dashed, color=red];"
+        ));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("graph LR\n"));
+        assert!(mermaid.contains("n0[\"elm/browser 1.0.2\"]"));
+        assert!(mermaid.contains("n0 -->|\"1.0.0 #60;= v #60; 2.0.0\"| n1"));
+        assert!(mermaid.contains("n0 -.->|"));
+    }
+}
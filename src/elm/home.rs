@@ -0,0 +1,243 @@
+use crate::elm::version::{Constraint, Version};
+use crate::elm::PackageInfo;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Compiler version whose package cache is used when the project doesn't pin one
+pub const DEFAULT_ELM_VERSION: &str = "0.19.1";
+
+/// The Elm home directory: `$ELM_HOME` when set, otherwise `$HOME/.elm`
+pub fn elm_home() -> Result<PathBuf, String> {
+    if let Some(elm_home) = std::env::var_os("ELM_HOME").filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(elm_home));
+    }
+
+    let home_dir =
+        std::env::var("HOME").map_err(|_| "Could not determine HOME directory".to_string())?;
+    Ok(PathBuf::from(home_dir).join(".elm"))
+}
+
+/// Package cache directories to search, in priority order.
+///
+/// `elm_version` is the project's `elm-version` field. An exact version picks
+/// that compiler's cache first; a range (from a package's elm.json) picks every
+/// installed compiler cache in the range, newest first. The default
+/// compiler's cache is always tried last since docs.json is the same format
+/// across 0.19.x.
+pub fn package_roots(elm_home: &Path, elm_version: Option<&str>) -> Vec<PathBuf> {
+    let mut versions: Vec<Version> = match elm_version.map(Constraint::parse) {
+        Some(Ok(constraint)) if constraint.is_exact() => vec![constraint.lower],
+        Some(Ok(constraint)) => {
+            let mut installed: Vec<Version> = version_dirs(elm_home)
+                .into_iter()
+                .filter(|v| constraint.contains(v))
+                .collect();
+            installed.sort_by(|a, b| b.cmp(a));
+            installed
+        }
+        _ => vec![],
+    };
+    let default = Version::parse(DEFAULT_ELM_VERSION).unwrap();
+    if !versions.contains(&default) {
+        versions.push(default);
+    }
+
+    versions
+        .into_iter()
+        .map(|version| elm_home.join(version.to_string()).join("packages"))
+        .collect()
+}
+
+/// Subdirectories of `dir` whose names are versions
+fn version_dirs(dir: &Path) -> Vec<Version> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| Version::parse(&entry.file_name().to_string_lossy()).ok())
+        .collect()
+}
+
+/// Every locally installed version of `author/name`, across the package roots
+pub fn installed_versions(author: &str, name: &str, elm_version: Option<&str>) -> Vec<Version> {
+    let Ok(elm_home) = elm_home() else {
+        return vec![];
+    };
+    let mut versions: Vec<Version> = package_roots(&elm_home, elm_version)
+        .iter()
+        .flat_map(|root| version_dirs(&root.join(author).join(name)))
+        .collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// The newest version of every package installed in one package root
+fn packages_in(root: &Path) -> Vec<PackageInfo> {
+    let subdirs = |dir: &Path| -> Vec<String> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    let mut packages = Vec::new();
+    for author in subdirs(root) {
+        for name in subdirs(&root.join(&author)) {
+            if let Some(version) = version_dirs(&root.join(&author).join(&name))
+                .into_iter()
+                .max()
+            {
+                packages.push(PackageInfo {
+                    author: author.clone(),
+                    name,
+                    version: version.to_string(),
+                });
+            }
+        }
+    }
+    packages
+}
+
+/// The newest installed version of every package in the local Elm cache,
+/// taken from the first package root that has the package
+pub fn installed_packages(elm_version: Option<&str>) -> Vec<PackageInfo> {
+    let Ok(elm_home) = elm_home() else {
+        return vec![];
+    };
+    let mut packages: Vec<PackageInfo> = Vec::new();
+    for root in package_roots(&elm_home, elm_version) {
+        for package in packages_in(&root) {
+            let known = packages
+                .iter()
+                .any(|p| p.author == package.author && p.name == package.name);
+            if !known {
+                packages.push(package);
+            }
+        }
+    }
+    packages
+}
+
+/// The version of `author/name` to read docs from for `constraint`: the
+/// pinned version, or the newest installed version in range. When nothing in
+/// range is installed, the lowest version in range is used so lookups can
+/// still fall back to the registry.
+pub fn resolve_version(
+    author: &str,
+    name: &str,
+    constraint: &Constraint,
+    elm_version: Option<&str>,
+) -> Version {
+    if constraint.is_exact() {
+        return constraint.lower;
+    }
+    constraint
+        .best(&installed_versions(author, name, elm_version))
+        .unwrap_or(constraint.lower)
+}
+
+/// Find the directory of an installed package, listing every path that was
+/// tried when it can't be found
+pub fn find_package_dir(
+    package: &PackageInfo,
+    elm_version: Option<&str>,
+) -> Result<PathBuf, String> {
+    let candidates: Vec<PathBuf> = package_roots(&elm_home()?, elm_version)
+        .into_iter()
+        .map(|root| {
+            root.join(&package.author)
+                .join(&package.name)
+                .join(&package.version)
+        })
+        .collect();
+
+    if let Some(found) = candidates.iter().find(|path| path.exists()) {
+        return Ok(found.clone());
+    }
+
+    let tried: Vec<String> = candidates
+        .iter()
+        .map(|path| format!("  - {}", path.display()))
+        .collect();
+    Err(format!(
+        "Package {}/{} version {} not found locally. Looked in:\n{}\nMake sure it's installed by running 'elm install' in an Elm project that uses this package, or set ELM_HOME if your packages live elsewhere.",
+        package.author,
+        package.name,
+        package.version,
+        tried.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::test_dir::TestDir;
+
+    #[test]
+    fn test_package_roots() {
+        let home = Path::new("/elm-home");
+
+        assert_eq!(
+            package_roots(home, None),
+            vec![PathBuf::from("/elm-home/0.19.1/packages")]
+        );
+        assert_eq!(
+            package_roots(home, Some("0.19.0")),
+            vec![
+                PathBuf::from("/elm-home/0.19.0/packages"),
+                PathBuf::from("/elm-home/0.19.1/packages")
+            ]
+        );
+        assert_eq!(
+            package_roots(home, Some("0.19.1")),
+            vec![PathBuf::from("/elm-home/0.19.1/packages")]
+        );
+        // Version ranges from package-type elm.json files use the default
+        // compiler when no compiler cache in range exists
+        assert_eq!(
+            package_roots(home, Some("0.19.0 <= v < 0.20.0")),
+            vec![PathBuf::from("/elm-home/0.19.1/packages")]
+        );
+    }
+
+    #[test]
+    fn test_package_roots_for_range() {
+        let home = TestDir::new("elm-home");
+        for version in ["0.19.0", "0.19.1", "0.18.0"] {
+            fs::create_dir_all(home.join(version).join("packages")).unwrap();
+        }
+
+        assert_eq!(
+            package_roots(&home, Some("0.19.0 <= v < 0.20.0")),
+            vec![home.join("0.19.1/packages"), home.join("0.19.0/packages")]
+        );
+    }
+
+    #[test]
+    fn test_packages_in() {
+        let root = TestDir::new("packages");
+        for dir in [
+            "elm/core/1.0.2",
+            "elm/core/1.0.5",
+            "elm/json/1.1.3",
+            "elm/empty",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let packages: Vec<String> = packages_in(&root)
+            .iter()
+            .map(|p| format!("{}/{} {}", p.author, p.name, p.version))
+            .collect();
+        assert_eq!(packages, vec!["elm/core 1.0.5", "elm/json 1.1.3"]);
+    }
+}
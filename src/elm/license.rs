@@ -0,0 +1,298 @@
+use crate::elm::graph::{DependencyGraph, Node};
+use crate::elm::reader::Section;
+use crate::elm::{fetcher, reader, search};
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+
+/// Environment variable holding the default deny-list, comma separated
+pub const DENY_LIST_ENV: &str = "ELM_PACKAGE_MCP_LICENSE_DENY";
+
+/// Group name for packages whose license couldn't be found
+pub const UNKNOWN: &str = "UNKNOWN";
+
+/// Where a package's license was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseSource {
+    /// The `license` field of the package's own elm.json
+    ElmJson,
+    /// The registry's search index, which lists the latest version's license
+    SearchIndex,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageLicense {
+    pub package: String,
+    pub version: String,
+    /// Where the project's elm.json lists the package; transitive
+    /// dependencies of a package project aren't listed
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub section: Option<Section>,
+    /// SPDX identifier, if it could be found
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<LicenseSource>,
+    pub denied: bool,
+}
+
+/// The packages using one license
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseGroup {
+    pub license: String,
+    pub count: usize,
+    pub packages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseReport {
+    pub deny_list: Vec<String>,
+    /// Grouped by SPDX identifier, alphabetically, with unknown licenses last
+    pub licenses: Vec<LicenseGroup>,
+    pub packages: Vec<PackageLicense>,
+    pub denied: Vec<PackageLicense>,
+    pub unknown: Vec<String>,
+}
+
+/// SPDX identifiers (or `*` patterns) from a comma-separated list
+pub fn parse_deny_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// The deny-list from `ELM_PACKAGE_MCP_LICENSE_DENY`
+pub fn deny_list_from_env() -> Vec<String> {
+    env::var(DENY_LIST_ENV)
+        .map(|value| parse_deny_list(&value))
+        .unwrap_or_default()
+}
+
+/// Whether `license` matches a deny-list entry. SPDX identifiers are compared
+/// case-insensitively, and a trailing `*` matches any suffix, so `GPL-*`
+/// covers `GPL-2.0-only` and `GPL-3.0-or-later`.
+pub fn is_denied(license: &str, deny_list: &[String]) -> bool {
+    let license = license.to_lowercase();
+    deny_list.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => license.starts_with(prefix),
+            None => license == pattern,
+        }
+    })
+}
+
+/// Build the report for the packages in a dependency graph. `licenses` maps
+/// `author/name` to the license in that package's elm.json; `registry_license`
+/// looks up packages missing from it.
+pub fn report(
+    nodes: &[Node],
+    licenses: &HashMap<String, String>,
+    registry_license: impl Fn(&str) -> Option<String>,
+    deny_list: &[String],
+) -> LicenseReport {
+    let packages: Vec<PackageLicense> = nodes
+        .iter()
+        .map(|node| {
+            let (license, source) = match licenses.get(&node.package) {
+                Some(license) => (Some(license.clone()), Some(LicenseSource::ElmJson)),
+                None => match registry_license(&node.package) {
+                    Some(license) => (Some(license), Some(LicenseSource::SearchIndex)),
+                    None => (None, None),
+                },
+            };
+            PackageLicense {
+                package: node.package.clone(),
+                version: node.version.clone(),
+                section: node.section,
+                denied: license
+                    .as_deref()
+                    .is_some_and(|license| is_denied(license, deny_list)),
+                license,
+                source,
+            }
+        })
+        .collect();
+
+    let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut unknown = Vec::new();
+    for package in &packages {
+        match &package.license {
+            Some(license) => groups
+                .entry(license.as_str())
+                .or_default()
+                .push(package.package.clone()),
+            None => unknown.push(package.package.clone()),
+        }
+    }
+    let mut licenses: Vec<LicenseGroup> = groups
+        .into_iter()
+        .map(|(license, packages)| LicenseGroup {
+            license: license.to_string(),
+            count: packages.len(),
+            packages,
+        })
+        .collect();
+    if !unknown.is_empty() {
+        licenses.push(LicenseGroup {
+            license: UNKNOWN.to_string(),
+            count: unknown.len(),
+            packages: unknown.clone(),
+        });
+    }
+
+    LicenseReport {
+        deny_list: deny_list.to_vec(),
+        licenses,
+        denied: packages.iter().filter(|p| p.denied).cloned().collect(),
+        packages,
+        unknown,
+    }
+}
+
+/// The license report for every package in the project's dependency tree,
+/// reading each package's elm.json from the local Elm cache or the registry.
+/// The registry's search index is only fetched if some elm.json can't be read.
+pub fn for_project(elm_json: &Value, include_test: bool, deny_list: &[String]) -> LicenseReport {
+    let elm_version = reader::get_elm_version(elm_json);
+    let licenses: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    let graph = DependencyGraph::for_project_with(elm_json, include_test, |package| {
+        let package_json = fetcher::fetch_elm_json(package, elm_version.as_deref())?;
+        if let Some(license) = package_json.get("license").and_then(|l| l.as_str()) {
+            licenses.borrow_mut().insert(
+                format!("{}/{}", package.author, package.name),
+                license.to_string(),
+            );
+        }
+        Ok(package_json)
+    });
+
+    let index = OnceCell::new();
+    report(
+        &graph.nodes,
+        &licenses.into_inner(),
+        |package| {
+            let index = index.get_or_init(|| search::fetch_search_index().ok());
+            index
+                .as_ref()?
+                .entries
+                .iter()
+                .find(|entry| entry.name == package)
+                .map(|entry| entry.license.clone())
+        },
+        deny_list,
+    )
+}
+
+impl LicenseReport {
+    /// The report as markdown: a summary table of licenses, then one row per
+    /// package
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("| License | Packages |\n| --- | --- |\n");
+        for group in &self.licenses {
+            out.push_str(&format!("| {} | {} |\n", group.license, group.count));
+        }
+
+        out.push_str("\n| Package | Version | Type | License | Source | Denied |\n");
+        out.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for package in &self.packages {
+            let section = package
+                .section
+                .and_then(|s| serde_json::to_value(s).ok())
+                .and_then(|s| s.as_str().map(str::to_string))
+                .unwrap_or_else(|| "transitive".to_string());
+            let source = match package.source {
+                Some(LicenseSource::ElmJson) => "elm.json",
+                Some(LicenseSource::SearchIndex) => "search index",
+                None => "",
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                package.package,
+                package.version,
+                section,
+                package.license.as_deref().unwrap_or(UNKNOWN),
+                source,
+                if package.denied { "**yes**" } else { "" }
+            ));
+        }
+
+        if !self.deny_list.is_empty() {
+            out.push_str(&format!(
+                "\nDeny-list: {}. {} denied package(s).\n",
+                self.deny_list.join(", "),
+                self.denied.len()
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(package: &str, section: Option<Section>) -> Node {
+        Node {
+            package: package.to_string(),
+            version: "1.0.0".to_string(),
+            section,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_is_denied() {
+        let deny = vec!["gpl-*".to_string(), "AGPL-3.0-only".to_string()];
+        assert!(is_denied("GPL-3.0-or-later", &deny));
+        assert!(is_denied("AGPL-3.0-only", &deny));
+        assert!(!is_denied("LGPL-2.1-only", &deny));
+        assert!(!is_denied("BSD-3-Clause", &deny));
+        assert!(!is_denied("MIT", &[]));
+    }
+
+    #[test]
+    fn test_report() {
+        let nodes = vec![
+            node("elm/core", Some(Section::Direct)),
+            node("someone/copyleft", Some(Section::Direct)),
+            node("elm/json", Some(Section::Indirect)),
+            node("someone/unpublished", None),
+        ];
+        let licenses = HashMap::from([
+            ("elm/core".to_string(), "BSD-3-Clause".to_string()),
+            ("elm/json".to_string(), "BSD-3-Clause".to_string()),
+        ]);
+        let registry =
+            |package: &str| (package == "someone/copyleft").then(|| "GPL-3.0-only".to_string());
+
+        let report = report(&nodes, &licenses, registry, &["GPL-*".to_string()]);
+        let groups: Vec<(&str, usize)> = report
+            .licenses
+            .iter()
+            .map(|g| (g.license.as_str(), g.count))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("BSD-3-Clause", 2), ("GPL-3.0-only", 1), (UNKNOWN, 1)]
+        );
+        assert_eq!(report.denied.len(), 1);
+        assert_eq!(report.denied[0].package, "someone/copyleft");
+        assert_eq!(report.denied[0].source, Some(LicenseSource::SearchIndex));
+        assert_eq!(report.unknown, vec!["someone/unpublished"]);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| BSD-3-Clause | 2 |"), "{markdown}");
+        assert!(
+            markdown.contains(
+                "| someone/copyleft | 1.0.0 | direct | GPL-3.0-only | search index | **yes** |"
+            ),
+            "{markdown}"
+        );
+        assert!(markdown.contains("| someone/unpublished | 1.0.0 | transitive | UNKNOWN |  |  |"));
+    }
+}
@@ -1,27 +1,35 @@
+pub mod annotation;
+pub mod api_diff;
+pub mod cache;
+pub mod doc_search;
+pub mod docs_cache;
+pub mod docs_mirror;
 pub mod fetcher;
+pub mod graph;
+pub mod home;
+pub mod license;
+pub mod module_docs;
+pub mod module_index;
+pub mod project_docs;
+pub mod ranking;
 pub mod reader;
+pub mod registry;
+pub mod releases;
 pub mod search;
+pub mod snapshot;
+pub mod solver;
+pub mod source;
+#[cfg(test)]
+pub mod test_dir;
+pub mod type_search;
+pub mod usage;
+pub mod version;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub author: String,
     pub name: String,
     pub version: String,
 }
-
-impl PackageInfo {
-    pub fn from_full_name(full_name: &str, version: &str) -> Option<Self> {
-        let parts: Vec<&str> = full_name.split('/').collect();
-        if parts.len() == 2 {
-            Some(PackageInfo {
-                author: parts[0].to_string(),
-                name: parts[1].to_string(),
-                version: version.to_string(),
-            })
-        } else {
-            None
-        }
-    }
-}
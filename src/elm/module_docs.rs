@@ -0,0 +1,200 @@
+use crate::elm::annotation::Type;
+use crate::elm::fetcher::Module;
+use std::collections::HashSet;
+
+/// Render a module's documentation as markdown, the way package.elm-lang.org
+/// does: the module comment's prose, with every `@docs a, b` line replaced by
+/// the signature and doc comment of each listed export.
+///
+/// Exports that no `@docs` line mentions are appended at the end so nothing
+/// is silently dropped.
+pub fn render(module: &Module) -> String {
+    let mut out = format!("# {}\n", module.name);
+    let mut rendered: HashSet<String> = HashSet::new();
+
+    for line in module.comment.trim().lines() {
+        match line.trim_start().strip_prefix("@docs") {
+            Some(names) => {
+                for name in names.split(',').map(export_name).filter(|n| !n.is_empty()) {
+                    match render_export(module, name) {
+                        Some(section) => {
+                            out.push('\n');
+                            out.push_str(&section);
+                            rendered.insert(name.to_string());
+                        }
+                        None => out.push_str(&format!("\n`{name}` is not exported.\n")),
+                    }
+                }
+            }
+            None => {
+                out.push('\n');
+                out.push_str(line);
+            }
+        }
+    }
+    out.push('\n');
+
+    let remaining: Vec<&str> = export_names(module)
+        .into_iter()
+        .filter(|name| !rendered.contains(*name))
+        .collect();
+    if !remaining.is_empty() {
+        out.push_str("\n## Other exports\n");
+        for name in remaining {
+            if let Some(section) = render_export(module, name) {
+                out.push('\n');
+                out.push_str(&section);
+            }
+        }
+    }
+
+    out
+}
+
+/// An export name as written in a `@docs` line; operators are listed in
+/// parentheses but stored without them
+fn export_name(entry: &str) -> &str {
+    let entry = entry.trim();
+    entry
+        .strip_prefix('(')
+        .and_then(|e| e.strip_suffix(')'))
+        .unwrap_or(entry)
+}
+
+fn export_names(module: &Module) -> Vec<&str> {
+    let unions = module.unions.iter().map(|u| u.name.as_str());
+    let aliases = module.aliases.iter().map(|a| a.name.as_str());
+    let values = module.values.iter().map(|v| v.name.as_str());
+    let binops = module.binops.iter().map(|b| b.name.as_str());
+    unions.chain(aliases).chain(values).chain(binops).collect()
+}
+
+/// The signature block and doc comment for one export
+fn render_export(module: &Module, name: &str) -> Option<String> {
+    let short = |typ: &Option<Type>, raw: &str| match typ {
+        Some(typ) => typ.shorten(&module.name).to_string(),
+        None => raw.to_string(),
+    };
+
+    let (heading, signature, comment) =
+        if let Some(union) = module.unions.iter().find(|u| u.name == name) {
+            let mut signature = type_head("type", &union.name, &union.args);
+            if union.parsed_cases.len() == union.cases.len() {
+                for (i, case) in union.parsed_cases.iter().enumerate() {
+                    let constructor = Type::Constructor {
+                        name: case.name.clone(),
+                        args: case.args.clone(),
+                    };
+                    let separator = if i == 0 { '=' } else { '|' };
+                    signature.push_str(&format!(
+                        "\n    {separator} {}",
+                        constructor.shorten(&module.name)
+                    ));
+                }
+            } else {
+                for (i, case) in union.cases.iter().enumerate() {
+                    let separator = if i == 0 { '=' } else { '|' };
+                    signature.push_str(&format!("\n    {separator} {}", case.join(" ")));
+                }
+            }
+            (union.name.clone(), signature, &union.comment)
+        } else if let Some(alias) = module.aliases.iter().find(|a| a.name == name) {
+            let signature = format!(
+                "{} =\n    {}",
+                type_head("type alias", &alias.name, &alias.args),
+                short(&alias.parsed_type, &alias.type_annotation)
+            );
+            (alias.name.clone(), signature, &alias.comment)
+        } else if let Some(value) = module.values.iter().find(|v| v.name == name) {
+            let signature = format!(
+                "{} : {}",
+                value.name,
+                short(&value.parsed_type, &value.type_annotation)
+            );
+            (value.name.clone(), signature, &value.comment)
+        } else if let Some(binop) = module.binops.iter().find(|b| b.name == name) {
+            let signature = format!(
+                "({}) : {}",
+                binop.name,
+                short(&binop.parsed_type, &binop.type_annotation)
+            );
+            (format!("({})", binop.name), signature, &binop.comment)
+        } else {
+            return None;
+        };
+
+    let mut section = format!("### `{heading}`\n\n```elm\n{signature}\n```\n");
+    let comment = comment.trim();
+    if !comment.is_empty() {
+        section.push('\n');
+        section.push_str(comment);
+        section.push('\n');
+    }
+    Some(section)
+}
+
+fn type_head(keyword: &str, name: &str, args: &[String]) -> String {
+    let mut head = format!("{keyword} {name}");
+    for arg in args {
+        head.push(' ');
+        head.push_str(arg);
+    }
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::fetcher::parse_docs;
+
+    fn maybe_module() -> Module {
+        parse_docs(
+            r#"[{
+                "name": "Maybe",
+                "comment": " This library fills a bunch of important niches.\n\n# Definition\n@docs Maybe\n\n# Common Helpers\n@docs withDefault, map\n",
+                "unions": [{
+                    "name": "Maybe",
+                    "comment": " Represent values that may or may not exist. ",
+                    "args": ["a"],
+                    "cases": [["Just", ["a"]], ["Nothing", []]]
+                }],
+                "aliases": [],
+                "values": [
+                    {"name": "withDefault", "comment": " Provide a default value. ", "type": "a -> Maybe.Maybe a -> a"},
+                    {"name": "map", "comment": " Transform a value. ", "type": "(a -> b) -> Maybe.Maybe a -> Maybe.Maybe b"},
+                    {"name": "andThen", "comment": " Chain together computations. ", "type": "(a -> Maybe.Maybe b) -> Maybe.Maybe a -> Maybe.Maybe b"}
+                ],
+                "binops": []
+            }]"#,
+        )
+        .unwrap()
+        .remove(0)
+    }
+
+    #[test]
+    fn test_render_expands_docs_in_order() {
+        let markdown = render(&maybe_module());
+
+        assert!(markdown.starts_with("# Maybe\n\nThis library fills"));
+        assert!(markdown.contains(
+            "### `Maybe`\n\n```elm\ntype Maybe a\n    = Just a\n    | Nothing\n```\n\nRepresent values"
+        ));
+        assert!(markdown.contains("```elm\nmap : (a -> b) -> Maybe a -> Maybe b\n```"));
+        assert!(!markdown.contains("@docs"));
+
+        let definition = markdown.find("# Definition").unwrap();
+        let with_default = markdown.find("### `withDefault`").unwrap();
+        let map = markdown.find("### `map`").unwrap();
+        assert!(definition < with_default && with_default < map);
+
+        // andThen has no @docs line, so it is listed afterwards
+        let other = markdown.find("## Other exports").unwrap();
+        assert!(map < other && other < markdown.find("### `andThen`").unwrap());
+    }
+
+    #[test]
+    fn test_export_name() {
+        assert_eq!(export_name(" (|>)"), "|>");
+        assert_eq!(export_name("map "), "map");
+    }
+}
@@ -0,0 +1,234 @@
+use crate::elm::reader::{self, Section};
+use crate::elm::search::{self, SearchEntry};
+use crate::elm::{fetcher, home, PackageInfo};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// How many registry packages to check when no local package has the module.
+/// The registry's search index doesn't list modules, so each candidate's
+/// elm.json has to be fetched.
+pub const MAX_REGISTRY_CANDIDATES: usize = 10;
+
+/// Where a package was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Listed in the project's elm.json
+    Project,
+    /// Installed in the local Elm cache
+    Cache,
+    /// Only in the package registry
+    Registry,
+}
+
+/// A package to check for the module
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub package: PackageInfo,
+    pub section: Option<Section>,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleMatch {
+    pub package: String,
+    pub version: String,
+    pub source: Source,
+    pub in_elm_json: bool,
+    /// Where elm.json lists the package
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub section: Option<Section>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleLookup {
+    pub module: String,
+    pub matches: Vec<ModuleMatch>,
+    pub searched_registry: bool,
+    /// Why the registry couldn't be searched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_error: Option<String>,
+}
+
+fn full_name(package: &PackageInfo) -> String {
+    format!("{}/{}", package.author, package.name)
+}
+
+/// The candidates that expose `module`, in the order given. `load` reads a
+/// candidate's elm.json; packages that can't be read are skipped.
+pub fn find_in(
+    module: &str,
+    candidates: &[Candidate],
+    load: impl Fn(&PackageInfo) -> Result<Value, String>,
+) -> Vec<ModuleMatch> {
+    candidates
+        .iter()
+        .filter(|candidate| {
+            load(&candidate.package)
+                .map(|elm_json| {
+                    reader::get_exposed_modules(&elm_json)
+                        .iter()
+                        .any(|m| m == module)
+                })
+                .unwrap_or(false)
+        })
+        .map(|candidate| ModuleMatch {
+            package: full_name(&candidate.package),
+            version: candidate.package.version.clone(),
+            source: candidate.source,
+            in_elm_json: candidate.section.is_some(),
+            section: candidate.section,
+        })
+        .collect()
+}
+
+/// Every package in the project's elm.json, direct dependencies first
+pub fn project_candidates(elm_json: &Value) -> Vec<Candidate> {
+    let elm_version = reader::get_elm_version(elm_json);
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for section in Section::ALL {
+        for dependency in reader::get_dependencies(elm_json, section) {
+            let package = dependency.resolve(elm_version.as_deref());
+            if !candidates.iter().any(|c| c.package == package) {
+                candidates.push(Candidate {
+                    package,
+                    section: Some(section),
+                    source: Source::Project,
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// Registry packages most likely to have `module`: the best fuzzy matches for
+/// its first segment (`Parser` for `Parser.Advanced`), at their latest version
+pub fn registry_candidates(
+    module: &str,
+    entries: &[SearchEntry],
+    exclude: &HashSet<String>,
+) -> Vec<Candidate> {
+    let query = module.split('.').next().unwrap_or(module);
+    search::fuzzy_search(query, entries, Some(exclude), MAX_REGISTRY_CANDIDATES)
+        .into_iter()
+        .filter_map(|result| {
+            let (author, name) = result.name.split_once('/')?;
+            Some(Candidate {
+                package: PackageInfo {
+                    author: author.to_string(),
+                    name: name.to_string(),
+                    version: result.version,
+                },
+                section: None,
+                source: Source::Registry,
+            })
+        })
+        .collect()
+}
+
+/// Find the packages exposing `module`: first those in the project's
+/// elm.json, then everything in the local Elm cache, and only when neither
+/// has it, likely candidates from the registry
+pub fn find_module(module: &str, elm_json: Option<&Value>, search_registry: bool) -> ModuleLookup {
+    let elm_version = elm_json.and_then(reader::get_elm_version);
+    let load = |package: &PackageInfo| fetcher::fetch_elm_json(package, elm_version.as_deref());
+
+    let mut candidates = elm_json.map(project_candidates).unwrap_or_default();
+    let mut checked: HashSet<String> = candidates.iter().map(|c| full_name(&c.package)).collect();
+    for package in home::installed_packages(elm_version.as_deref()) {
+        if checked.insert(full_name(&package)) {
+            candidates.push(Candidate {
+                package,
+                section: None,
+                source: Source::Cache,
+            });
+        }
+    }
+
+    let mut lookup = ModuleLookup {
+        module: module.to_string(),
+        matches: find_in(module, &candidates, load),
+        searched_registry: false,
+        registry_error: None,
+    };
+    if !lookup.matches.is_empty() || !search_registry {
+        return lookup;
+    }
+
+    lookup.searched_registry = true;
+    match search::fetch_search_index() {
+        Ok(index) => {
+            let candidates = registry_candidates(module, &index.entries, &checked);
+            lookup.matches = find_in(module, &candidates, load);
+        }
+        Err(e) => lookup.registry_error = Some(e),
+    }
+    lookup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(name: &str, summary: &str) -> SearchEntry {
+        SearchEntry {
+            name: name.to_string(),
+            summary: summary.to_string(),
+            license: "BSD-3-Clause".to_string(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    fn load(package: &PackageInfo) -> Result<Value, String> {
+        let modules: &[&str] = match package.name.as_str() {
+            "html" => &["Html", "Html.Lazy"],
+            "parser" => &["Parser", "Parser.Advanced"],
+            "elm-parser-extras" => &["Parser.Extras"],
+            _ => return Err("not found".to_string()),
+        };
+        Ok(json!({"exposed-modules": modules}))
+    }
+
+    #[test]
+    fn test_find_in_project() {
+        let elm_json = json!({
+            "type": "application",
+            "elm-version": "0.19.1",
+            "dependencies": {
+                "direct": {"elm/html": "1.0.0"},
+                "indirect": {"elm/parser": "1.1.0", "elm/broken": "1.0.0"}
+            }
+        });
+        let candidates = project_candidates(&elm_json);
+
+        let matches = find_in("Parser.Advanced", &candidates, load);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].package, "elm/parser");
+        assert_eq!(matches[0].version, "1.1.0");
+        assert_eq!(matches[0].section, Some(Section::Indirect));
+        assert!(matches[0].in_elm_json);
+
+        assert!(find_in("Html.Keyed", &candidates, load).is_empty());
+    }
+
+    #[test]
+    fn test_registry_candidates() {
+        let entries = vec![
+            entry("elm/html", "Fast HTML, rendered with virtual DOM diffing"),
+            entry("elm/parser", "a parsing library, focused on simplicity"),
+            entry("someone/elm-parser-extras", "Parser helpers"),
+        ];
+        let exclude = HashSet::from(["elm/parser".to_string()]);
+
+        let candidates = registry_candidates("Parser.Extras", &entries, &exclude);
+        let names: Vec<String> = candidates.iter().map(|c| full_name(&c.package)).collect();
+        assert_eq!(names, vec!["someone/elm-parser-extras"]);
+
+        let matches = find_in("Parser.Extras", &candidates, load);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].source, Source::Registry);
+        assert!(!matches[0].in_elm_json);
+    }
+}
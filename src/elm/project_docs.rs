@@ -0,0 +1,438 @@
+use crate::elm::fetcher::{Alias, Module, Union, Value};
+use crate::elm::source::{self, ProjectFile};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A module of the project itself, documented like a package module
+#[derive(Debug, Clone)]
+pub struct ProjectModule {
+    /// Path relative to the project directory
+    pub path: String,
+    pub module: Module,
+}
+
+/// What a module header exposes
+#[derive(Debug, Clone, PartialEq)]
+enum Exposing {
+    All,
+    /// Exposed names, with whether a type's constructors are exposed too
+    Only(HashMap<String, bool>),
+}
+
+impl Exposing {
+    fn parse(list: &str) -> Exposing {
+        let inner = list
+            .trim()
+            .strip_prefix('(')
+            .and_then(|l| l.strip_suffix(')'))
+            .unwrap_or(list);
+        if inner.trim() == ".." {
+            return Exposing::All;
+        }
+        Exposing::Only(
+            split_top_level(inner, ',')
+                .iter()
+                .map(|item| match item.split_once('(') {
+                    // `(|>)` is an operator, `Type(..)` exposes constructors
+                    Some(("", _)) => (item.to_string(), false),
+                    Some((name, _)) => (name.trim().to_string(), true),
+                    None => (item.to_string(), false),
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether `name` is exposed, and if so whether its constructors are
+    fn get(&self, name: &str) -> Option<bool> {
+        match self {
+            Exposing::All => Some(true),
+            Exposing::Only(names) => names.get(name).copied(),
+        }
+    }
+}
+
+/// Split `text` on `separator` where it isn't nested in brackets
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    for c in text.chars() {
+        match c {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            _ => {}
+        }
+        if c == separator && depth == 0 {
+            parts.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Split a constructor like `Node String (List Tree)` into its name and
+/// argument types
+fn split_words(text: &str) -> Vec<String> {
+    split_top_level(text, ' ')
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn starts_with(chars: &[char], pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(i, p)| chars.get(i) == Some(&p))
+}
+
+/// Track comment nesting and multi-line strings across one line, so we know
+/// whether the next line starts at the top level
+fn scan_line(line: &str, depth: &mut usize, in_string: &mut bool) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        if *in_string {
+            if starts_with(rest, "\"\"\"") {
+                *in_string = false;
+                i += 3;
+            } else {
+                i += if rest[0] == '\\' { 2 } else { 1 };
+            }
+        } else if *depth > 0 {
+            if starts_with(rest, "{-") {
+                *depth += 1;
+                i += 2;
+            } else if starts_with(rest, "-}") {
+                *depth -= 1;
+                i += 2;
+            } else {
+                i += 1;
+            }
+        } else if starts_with(rest, "--") {
+            break;
+        } else if starts_with(rest, "{-") {
+            *depth += 1;
+            i += 2;
+        } else if starts_with(rest, "\"\"\"") {
+            *in_string = true;
+            i += 3;
+        } else if rest[0] == '"' || rest[0] == '\'' {
+            // Skip a single-line string or character literal
+            let quote = rest[0];
+            i += 1;
+            while i < chars.len() && chars[i] != quote {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Split a source file into top-level chunks. A chunk starts at each line
+/// that begins in column 0 outside a comment or string, and runs until the
+/// next one.
+fn chunks(source: &str) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let (mut depth, mut in_string) = (0, false);
+    for line in source.lines() {
+        let top_level = depth == 0 && !in_string;
+        if top_level && line.starts_with(|c: char| !c.is_whitespace()) {
+            chunks.push(String::new());
+        }
+        if let Some(chunk) = chunks.last_mut() {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+        scan_line(line, &mut depth, &mut in_string);
+    }
+    chunks
+}
+
+/// Collapse runs of whitespace, as docs.json annotations have no line breaks.
+/// Leading commas in elm-format's layout become `a, b`.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+}
+
+fn is_value_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_lowercase())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parse the declarations of an Elm source file into the docs.json module
+/// shape, keeping only what the header exposes. Type annotations are as
+/// written in the source rather than fully qualified, and values without an
+/// annotation have an empty type. `default_name` is used when the file has
+/// no module header.
+pub fn parse_module(source: &str, default_name: &str) -> Module {
+    let mut module = Module {
+        name: default_name.to_string(),
+        comment: String::new(),
+        unions: vec![],
+        aliases: vec![],
+        values: vec![],
+        binops: vec![],
+    };
+    let mut exposing = Exposing::All;
+    let mut pending_doc: Option<String> = None;
+    let mut after_header = false;
+
+    for chunk in chunks(source) {
+        if let Some(doc) = chunk.trim_end().strip_prefix("{-|") {
+            let doc = doc.strip_suffix("-}").unwrap_or(doc).trim().to_string();
+            if after_header {
+                module.comment = doc;
+            } else {
+                pending_doc = Some(doc);
+            }
+            after_header = false;
+            continue;
+        }
+        after_header = false;
+        let text = normalize(&source::strip_comments(&chunk));
+        if text.is_empty() {
+            continue;
+        }
+        let doc = pending_doc.take().unwrap_or_default();
+
+        let header = text
+            .strip_prefix("port ")
+            .or_else(|| text.strip_prefix("effect "))
+            .unwrap_or(&text);
+        if let Some(header) = header.strip_prefix("module ") {
+            if let Some(name) = header.split_whitespace().next() {
+                module.name = name.to_string();
+            }
+            if let Some((_, list)) = header.split_once("exposing") {
+                exposing = Exposing::parse(list);
+            }
+            after_header = true;
+        } else if let Some(alias) = text.strip_prefix("type alias ") {
+            let Some((head, body)) = alias.split_once('=') else {
+                continue;
+            };
+            let mut words = head.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+            module.aliases.push(Alias {
+                name: name.to_string(),
+                comment: doc,
+                args: words.map(|w| w.to_string()).collect(),
+                type_annotation: body.trim().to_string(),
+                parsed_type: None,
+            });
+        } else if let Some(union) = text.strip_prefix("type ") {
+            let (head, body) = union.split_once('=').unwrap_or((union, ""));
+            let mut words = head.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+            module.unions.push(Union {
+                name: name.to_string(),
+                comment: doc,
+                args: words.map(|w| w.to_string()).collect(),
+                cases: split_top_level(body, '|')
+                    .iter()
+                    .map(|case| split_words(case))
+                    .filter(|case| !case.is_empty())
+                    .collect(),
+                parsed_cases: vec![],
+            });
+        } else if text.starts_with("import ") || text.starts_with("infix ") {
+            continue;
+        } else {
+            // `name : type` annotations (including ports), or definitions
+            let declaration = text.strip_prefix("port ").unwrap_or(&text);
+            if let Some((name, annotation)) = declaration.split_once(':') {
+                let name = name.trim();
+                if is_value_name(name) {
+                    module.values.push(Value {
+                        name: name.to_string(),
+                        type_annotation: annotation.trim().to_string(),
+                        comment: doc,
+                        parsed_type: None,
+                    });
+                    continue;
+                }
+            }
+            let name = declaration.split_whitespace().next().unwrap_or("");
+            let annotated = module.values.iter().any(|v| v.name == name);
+            if is_value_name(name) && !annotated && declaration.contains('=') {
+                module.values.push(Value {
+                    name: name.to_string(),
+                    type_annotation: String::new(),
+                    comment: doc,
+                    parsed_type: None,
+                });
+            }
+        }
+    }
+
+    module.values.retain(|v| exposing.get(&v.name).is_some());
+    module.aliases.retain(|a| exposing.get(&a.name).is_some());
+    module.unions.retain(|u| exposing.get(&u.name).is_some());
+    for union in &mut module.unions {
+        // Opaque types keep their constructors private, as in docs.json
+        if exposing.get(&union.name) != Some(true) {
+            union.cases.clear();
+        }
+    }
+    module
+}
+
+fn read_module(project_file: &ProjectFile) -> Result<ProjectModule, String> {
+    let content = fs::read_to_string(&project_file.file)
+        .map_err(|e| format!("Failed to read {}: {e}", project_file.file.display()))?;
+    Ok(ProjectModule {
+        path: project_file.path.clone(),
+        module: parse_module(&content, &project_file.module),
+    })
+}
+
+/// Every module in the project's source directories
+pub fn project_modules(
+    elm_json: &JsonValue,
+    project_dir: &Path,
+) -> Result<Vec<ProjectModule>, String> {
+    source::project_files(elm_json, project_dir)?
+        .iter()
+        .map(read_module)
+        .collect()
+}
+
+/// One module of the project, found by its name
+pub fn project_module(
+    elm_json: &JsonValue,
+    project_dir: &Path,
+    name: &str,
+) -> Result<ProjectModule, String> {
+    let files = source::project_files(elm_json, project_dir)?;
+    let project_file = files
+        .iter()
+        .find(|f| f.module == name)
+        .ok_or_else(|| format!("Module '{name}' not found in the project's source directories"))?;
+    read_module(project_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"module Page.Home exposing
+    ( Model, Msg(..), init
+    , view
+    )
+
+{-| The home page.
+-}
+
+import Html exposing (Html)
+
+
+{-| Page state
+-}
+type alias Model =
+    { count : Int
+    , name : String -- display name
+    }
+
+
+type Msg
+    = Increment
+    | Rename String
+    | Load (Result String (List Int))
+
+
+type Hidden = Hidden
+
+
+{-| Start at zero.
+
+    init "x"
+
+-}
+init : String -> Model
+init name =
+    { count = 0, name = name }
+
+
+view : Model
+    -> Html Msg
+view model =
+    Html.text """
+not : a declaration
+"""
+
+
+helper x =
+    x
+"#;
+
+    #[test]
+    fn test_parse_module() {
+        let module = parse_module(SOURCE, "Ignored");
+        assert_eq!(module.name, "Page.Home");
+        assert_eq!(module.comment, "The home page.");
+
+        assert_eq!(module.aliases.len(), 1);
+        assert_eq!(module.aliases[0].name, "Model");
+        assert_eq!(module.aliases[0].comment, "Page state");
+        assert_eq!(
+            module.aliases[0].type_annotation,
+            "{ count : Int, name : String }"
+        );
+
+        assert_eq!(module.unions.len(), 1);
+        assert_eq!(
+            module.unions[0].cases,
+            vec![
+                vec!["Increment".to_string()],
+                vec!["Rename".to_string(), "String".to_string()],
+                vec!["Load".to_string(), "(Result String (List Int))".to_string()],
+            ]
+        );
+
+        let values: Vec<(&str, &str)> = module
+            .values
+            .iter()
+            .map(|v| (v.name.as_str(), v.type_annotation.as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![("init", "String -> Model"), ("view", "Model -> Html Msg")]
+        );
+        assert!(module.values[0].comment.contains("init \"x\""));
+    }
+
+    #[test]
+    fn test_exposing() {
+        let module = parse_module(
+            "module Counter exposing (Counter, increment)\n\ntype Counter = Counter Int\n\nincrement : Counter -> Counter\nincrement (Counter n) = Counter (n + 1)\n",
+            "Counter",
+        );
+        assert_eq!(module.unions[0].name, "Counter");
+        assert!(module.unions[0].cases.is_empty());
+        assert_eq!(module.values[0].name, "increment");
+
+        // No header: everything is exposed, and unannotated values are kept
+        let module = parse_module("main =\n    text \"hi\"\n", "Main");
+        assert_eq!(module.name, "Main");
+        assert_eq!(module.values[0].name, "main");
+        assert_eq!(module.values[0].type_annotation, "");
+    }
+}
@@ -0,0 +1,209 @@
+use crate::elm::reader;
+use crate::elm::snapshot::{self, PackageRegistry, SnapshotRegistry};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Authors whose packages are boosted: the core team's, and the collections
+/// the community maintains together
+pub const AUTHOR_BOOSTS: &[(&str, u32)] =
+    &[("elm", 30), ("elm-explorations", 20), ("elm-community", 15)];
+
+/// How many packages depend on each package (`author/name`)
+pub type Popularity = HashMap<String, usize>;
+
+/// Points for a query term matching a package name. `elm-` prefixes are so
+/// common that `elm-css` counts as an exact match for `css`, and an `elm`
+/// segment only counts when the term is `elm` itself.
+fn name_score(term: &str, package: &str) -> u32 {
+    let (author, name) = package.split_once('/').unwrap_or(("", package));
+    let name = name.to_lowercase();
+    let bare = name.strip_prefix("elm-").unwrap_or(&name);
+    let mut segments = name.split('-').filter(|s| *s != "elm" || term == "elm");
+
+    if name == term || bare == term {
+        100
+    } else if segments.clone().any(|s| s == term) {
+        40
+    } else if segments.any(|s| s.starts_with(term)) {
+        20
+    } else if name.contains(term) || author.eq_ignore_ascii_case(term) {
+        10
+    } else {
+        0
+    }
+}
+
+/// Points for a query term appearing as a word of a package summary
+fn summary_score(term: &str, summary: &str) -> u32 {
+    let summary = summary.to_lowercase();
+    let mut words = summary
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty());
+    if words.clone().any(|w| w == term) {
+        8
+    } else if words.any(|w| w.starts_with(term)) {
+        4
+    } else {
+        0
+    }
+}
+
+pub fn author_boost(package: &str) -> u32 {
+    let author = package.split('/').next().unwrap_or("");
+    AUTHOR_BOOSTS
+        .iter()
+        .find(|(a, _)| *a == author)
+        .map(|(_, boost)| *boost)
+        .unwrap_or(0)
+}
+
+/// Points for being depended on: 5 per doubling of dependents, up to 40
+pub fn popularity_boost(dependents: usize) -> u32 {
+    ((usize::BITS - dependents.leading_zeros()) * 5).min(40)
+}
+
+/// The ranking score of a package that matched a query. Name terms (free text
+/// and `name:` terms) score most for matching a whole name or name segment;
+/// summary terms (free text and `summary:` terms) score for matching whole
+/// words. The fuzzy matcher's score only breaks ties between similar
+/// matches, so a lucky subsequence in a summary can't outrank the canonical
+/// package. Author and popularity boosts are added on top.
+pub fn score(
+    package: &str,
+    summary: &str,
+    name_terms: &[&str],
+    summary_terms: &[&str],
+    fuzzy: u32,
+    popularity: Option<&Popularity>,
+) -> u32 {
+    let relevance: u32 = name_terms
+        .iter()
+        .map(|term| name_score(&term.to_lowercase(), package))
+        .sum::<u32>()
+        + summary_terms
+            .iter()
+            .map(|term| summary_score(&term.to_lowercase(), summary))
+            .sum::<u32>();
+    let dependents = popularity
+        .and_then(|p| p.get(package))
+        .copied()
+        .unwrap_or(0);
+    relevance + (fuzzy / 8).min(30) + author_boost(package) + popularity_boost(dependents)
+}
+
+/// Count the packages depending on each package in `registry`, judging each
+/// package by the elm.json of its latest version
+pub fn dependents(registry: &SnapshotRegistry) -> Popularity {
+    let mut counts = Popularity::new();
+    for (package, version) in registry.latest_versions() {
+        let Ok(elm_json) = registry.package_elm_json(&package, &version) else {
+            continue;
+        };
+        for dependency in reader::get_direct_dependencies(&elm_json) {
+            *counts
+                .entry(format!("{}/{}", dependency.author, dependency.name))
+                .or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Popularity from the registry snapshot named by
+/// `$ELM_PACKAGE_MCP_REGISTRY_SNAPSHOT`, computed once per process. None
+/// without a snapshot.
+pub fn snapshot_popularity() -> Option<&'static Popularity> {
+    static POPULARITY: OnceLock<Option<Popularity>> = OnceLock::new();
+    POPULARITY
+        .get_or_init(|| {
+            let dir = std::env::var_os(snapshot::SNAPSHOT_DIR_ENV).filter(|v| !v.is_empty())?;
+            let registry = SnapshotRegistry::load(PathBuf::from(dir)).ok()?;
+            Some(dependents(&registry))
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::search::{search, SearchEntry, SearchQuery};
+    use crate::elm::snapshot::tests::fixture_registry;
+    use serde::Deserialize;
+    use std::path::Path;
+
+    #[derive(Deserialize)]
+    struct CorpusQuery {
+        query: String,
+        expected_top: String,
+    }
+
+    fn fixture(file: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/search")
+            .join(file);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_name_score() {
+        assert_eq!(name_score("json", "elm/json"), 100);
+        assert_eq!(name_score("css", "rtfeldman/elm-css"), 100);
+        assert_eq!(name_score("json", "elm-community/json-extra"), 40);
+        assert_eq!(name_score("iso", "rtfeldman/elm-iso8601-date-strings"), 20);
+        assert_eq!(name_score("elm", "rtfeldman/elm-css"), 40);
+        assert_eq!(name_score("son", "elm/json"), 10);
+        assert_eq!(name_score("xml", "elm/json"), 0);
+    }
+
+    #[test]
+    fn test_popularity_boost() {
+        assert_eq!(popularity_boost(0), 0);
+        assert_eq!(popularity_boost(1), 5);
+        assert_eq!(popularity_boost(4), 15);
+        assert_eq!(popularity_boost(1_000_000), 40);
+    }
+
+    #[test]
+    fn test_dependents() {
+        let popularity = dependents(&fixture_registry());
+        assert!(popularity["elm/core"] > popularity["elm/json"]);
+        assert!(!popularity.contains_key("old/elm-18"));
+    }
+
+    /// Queries from tests/fixtures/search/queries.json must rank the expected
+    /// package first among the packages in search.json
+    #[test]
+    fn test_ranking_corpus() {
+        let entries: Vec<SearchEntry> = serde_json::from_str(&fixture("search.json")).unwrap();
+        let queries: Vec<CorpusQuery> = serde_json::from_str(&fixture("queries.json")).unwrap();
+
+        let failures: Vec<String> = queries
+            .iter()
+            .filter_map(|q| {
+                let results = search(&SearchQuery::parse(&q.query), &entries, None, None);
+                let top: Vec<&str> = results.iter().take(3).map(|r| r.name.as_str()).collect();
+                (top.first() != Some(&q.expected_top.as_str()))
+                    .then(|| format!("'{}': expected {}, got {:?}", q.query, q.expected_top, top))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_popularity_breaks_ties() {
+        let entry = |name: &str| SearchEntry {
+            name: name.to_string(),
+            summary: "Charts".to_string(),
+            license: "BSD-3-Clause".to_string(),
+            version: "1.0.0".to_string(),
+        };
+        let entries = vec![entry("a/elm-charts"), entry("b/elm-charts")];
+        let popularity = Popularity::from([("b/elm-charts".to_string(), 12)]);
+
+        let query = SearchQuery::parse("charts");
+        let without = search(&query, &entries, None, None);
+        assert_eq!(without[0].name, "a/elm-charts");
+        let with = search(&query, &entries, None, Some(&popularity));
+        assert_eq!(with[0].name, "b/elm-charts");
+    }
+}
@@ -1,5 +1,8 @@
+use crate::elm::home;
+use crate::elm::version::Constraint;
 use crate::elm::PackageInfo;
-use serde_json::Value;
+use serde::Serialize;
+use serde_json::{Map, Value};
 use std::fs;
 
 pub fn read_elm_json(path: &str) -> Result<Value, String> {
@@ -8,38 +11,374 @@ pub fn read_elm_json(path: &str) -> Result<Value, String> {
     serde_json::from_str(&content).map_err(|e| format!("Failed to parse elm.json: {e}"))
 }
 
-pub fn get_direct_packages(elm_json: &Value) -> Vec<PackageInfo> {
-    let mut packages = Vec::new();
+/// The `type` field of elm.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectType {
+    /// Exact versions, split into `dependencies.direct` and `dependencies.indirect`
+    Application,
+    /// Version ranges in a flat `dependencies` map, with no indirect dependencies
+    Package,
+}
 
-    if let Some(deps) = elm_json.get("dependencies").and_then(|d| d.get("direct")) {
-        if let Some(deps_obj) = deps.as_object() {
-            for (name, version) in deps_obj {
-                if let Some(version_str) = version.as_str() {
-                    if let Some(package) = PackageInfo::from_full_name(name, version_str) {
-                        packages.push(package);
-                    }
-                }
-            }
+pub fn get_project_type(elm_json: &Value) -> ProjectType {
+    match elm_json.get("type").and_then(|t| t.as_str()) {
+        Some("package") => ProjectType::Package,
+        _ => ProjectType::Application,
+    }
+}
+
+/// The `elm-version` field: an exact version for applications, a range for packages
+pub fn get_elm_version(elm_json: &Value) -> Option<String> {
+    elm_json
+        .get("elm-version")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+}
+
+/// The module names in a JSON list
+fn module_names(list: &Value) -> Vec<String> {
+    list.as_array()
+        .map(|modules| {
+            modules
+                .iter()
+                .filter_map(|m| m.as_str().map(|m| m.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The `exposed-modules` of a package's elm.json, which is either a list or
+/// an object of lists grouped under headings for the package website
+pub fn get_exposed_modules(elm_json: &Value) -> Vec<String> {
+    match elm_json.get("exposed-modules") {
+        Some(Value::Object(groups)) => groups.values().flat_map(module_names).collect(),
+        Some(list) => module_names(list),
+        None => vec![],
+    }
+}
+
+/// Exposed modules listed under one heading of a package's elm.json
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModuleGroup {
+    pub heading: String,
+    pub modules: Vec<String>,
+}
+
+/// The headings `exposed-modules` is grouped under, in order. Empty when it
+/// is a plain list.
+pub fn get_module_groups(elm_json: &Value) -> Vec<ModuleGroup> {
+    match elm_json.get("exposed-modules") {
+        Some(Value::Object(groups)) => groups
+            .iter()
+            .map(|(heading, modules)| ModuleGroup {
+                heading: heading.clone(),
+                modules: module_names(modules),
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// What a package's own elm.json says about it
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageMetadata {
+    /// `author/name`
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub summary: Option<String>,
+    pub license: Option<String>,
+    /// The range of compiler versions the package works with
+    pub elm_version: Option<String>,
+    pub exposed_modules: Vec<String>,
+    /// The headings the package website lists the modules under, if any
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub module_groups: Vec<ModuleGroup>,
+    /// `author/name` to version range
+    pub dependencies: Map<String, Value>,
+    pub test_dependencies: Map<String, Value>,
+}
+
+/// Read a package's metadata from its elm.json
+pub fn get_package_metadata(elm_json: &Value) -> PackageMetadata {
+    let field = |key: &str| {
+        elm_json
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+    let constraints = |dependencies: Vec<Dependency>| -> Map<String, Value> {
+        dependencies
+            .into_iter()
+            .map(|d| {
+                (
+                    format!("{}/{}", d.author, d.name),
+                    Value::String(d.constraint.to_string()),
+                )
+            })
+            .collect()
+    };
+    PackageMetadata {
+        name: field("name"),
+        version: field("version"),
+        summary: field("summary"),
+        license: field("license"),
+        elm_version: get_elm_version(elm_json),
+        exposed_modules: get_exposed_modules(elm_json),
+        module_groups: get_module_groups(elm_json),
+        dependencies: constraints(get_direct_dependencies(elm_json)),
+        test_dependencies: constraints(get_test_dependencies(elm_json)),
+    }
+}
+
+/// A dependency as declared in elm.json, before resolving it to a version
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub author: String,
+    pub name: String,
+    pub constraint: Constraint,
+}
+
+impl Dependency {
+    /// The package to read docs from: the pinned version for applications,
+    /// the newest locally installed version in range for packages
+    pub fn resolve(&self, elm_version: Option<&str>) -> PackageInfo {
+        let version =
+            home::resolve_version(&self.author, &self.name, &self.constraint, elm_version);
+        PackageInfo {
+            author: self.author.clone(),
+            name: self.name.clone(),
+            version: version.to_string(),
         }
     }
+}
 
-    packages
+/// Parse a `"author/name": "version or range"` map, skipping malformed entries
+fn dependencies_in(section: Option<&Value>) -> Vec<Dependency> {
+    let Some(deps_obj) = section.and_then(|s| s.as_object()) else {
+        return vec![];
+    };
+
+    deps_obj
+        .iter()
+        .filter_map(|(full_name, constraint)| {
+            let (author, name) = full_name.split_once('/')?;
+            let constraint = Constraint::parse(constraint.as_str()?).ok()?;
+            Some(Dependency {
+                author: author.to_string(),
+                name: name.to_string(),
+                constraint,
+            })
+        })
+        .collect()
+}
+
+/// A group of dependencies in elm.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    Direct,
+    Indirect,
+    TestDirect,
+    TestIndirect,
 }
 
-pub fn get_indirect_packages(elm_json: &Value) -> Vec<PackageInfo> {
-    let mut packages = Vec::new();
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Direct,
+        Section::Indirect,
+        Section::TestDirect,
+        Section::TestIndirect,
+    ];
 
-    if let Some(deps) = elm_json.get("dependencies").and_then(|d| d.get("indirect")) {
-        if let Some(deps_obj) = deps.as_object() {
-            for (name, version) in deps_obj {
-                if let Some(version_str) = version.as_str() {
-                    if let Some(package) = PackageInfo::from_full_name(name, version_str) {
-                        packages.push(package);
-                    }
-                }
-            }
+    pub fn is_test(self) -> bool {
+        matches!(self, Section::TestDirect | Section::TestIndirect)
+    }
+
+    pub fn is_indirect(self) -> bool {
+        matches!(self, Section::Indirect | Section::TestIndirect)
+    }
+}
+
+/// The dependencies in one section of elm.json. Package projects have flat
+/// `dependencies` and `test-dependencies` maps, so their indirect sections are
+/// always empty.
+pub fn get_dependencies(elm_json: &Value, section: Section) -> Vec<Dependency> {
+    let key = if section.is_test() {
+        "test-dependencies"
+    } else {
+        "dependencies"
+    };
+    let deps = elm_json.get(key);
+    match (get_project_type(elm_json), section) {
+        (ProjectType::Application, Section::Direct | Section::TestDirect) => {
+            dependencies_in(deps.and_then(|d| d.get("direct")))
+        }
+        (ProjectType::Application, Section::Indirect | Section::TestIndirect) => {
+            dependencies_in(deps.and_then(|d| d.get("indirect")))
         }
+        (ProjectType::Package, Section::Direct | Section::TestDirect) => dependencies_in(deps),
+        (ProjectType::Package, Section::Indirect | Section::TestIndirect) => vec![],
     }
+}
+
+pub fn get_direct_dependencies(elm_json: &Value) -> Vec<Dependency> {
+    get_dependencies(elm_json, Section::Direct)
+}
+
+pub fn get_indirect_dependencies(elm_json: &Value) -> Vec<Dependency> {
+    get_dependencies(elm_json, Section::Indirect)
+}
+
+/// Direct and indirect test dependencies
+pub fn get_test_dependencies(elm_json: &Value) -> Vec<Dependency> {
+    let mut dependencies = get_dependencies(elm_json, Section::TestDirect);
+    dependencies.extend(get_dependencies(elm_json, Section::TestIndirect));
+    dependencies
+}
 
+fn resolve_all(elm_json: &Value, dependencies: Vec<Dependency>) -> Vec<PackageInfo> {
+    let elm_version = get_elm_version(elm_json);
+    dependencies
+        .iter()
+        .map(|dep| dep.resolve(elm_version.as_deref()))
+        .collect()
+}
+
+/// The packages in `sections`, without duplicates (a test dependency may also
+/// be an indirect dependency of the application)
+pub fn get_packages(elm_json: &Value, sections: &[Section]) -> Vec<PackageInfo> {
+    let mut packages: Vec<PackageInfo> = Vec::new();
+    for &section in sections {
+        for package in resolve_all(elm_json, get_dependencies(elm_json, section)) {
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+    }
     packages
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_application_dependencies() {
+        let elm_json = json!({
+            "type": "application",
+            "elm-version": "0.19.1",
+            "dependencies": {
+                "direct": {"elm/core": "1.0.5", "elm/html": "1.0.0"},
+                "indirect": {"elm/json": "1.1.3"}
+            },
+            "test-dependencies": {
+                "direct": {"elm-explorations/test": "2.1.1"},
+                "indirect": {"elm/random": "1.0.0"}
+            }
+        });
+
+        assert_eq!(get_project_type(&elm_json), ProjectType::Application);
+        let direct = get_packages(&elm_json, &[Section::Direct]);
+        assert_eq!(direct.len(), 2);
+        assert_eq!(direct[0].version, "1.0.5");
+        assert_eq!(
+            get_packages(&elm_json, &[Section::Indirect])[0].name,
+            "json"
+        );
+
+        let test: Vec<String> =
+            get_packages(&elm_json, &[Section::TestDirect, Section::TestIndirect])
+                .into_iter()
+                .map(|p| format!("{}/{}", p.author, p.name))
+                .collect();
+        assert_eq!(test, vec!["elm-explorations/test", "elm/random"]);
+
+        let everything = get_packages(&elm_json, &Section::ALL);
+        assert_eq!(everything.len(), 5);
+    }
+
+    #[test]
+    fn test_package_dependencies() {
+        let elm_json = json!({
+            "type": "package",
+            "elm-version": "0.19.0 <= v < 0.20.0",
+            "dependencies": {
+                "elm/core": "1.0.0 <= v < 2.0.0",
+                "not-a-package": "1.0.0 <= v < 2.0.0",
+                "elm/json": "not a range"
+            },
+            "test-dependencies": {
+                "elm-explorations/test": "2.0.0 <= v < 3.0.0"
+            }
+        });
+
+        assert_eq!(get_project_type(&elm_json), ProjectType::Package);
+        let direct = get_direct_dependencies(&elm_json);
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].constraint.to_string(), "1.0.0 <= v < 2.0.0");
+        assert!(get_indirect_dependencies(&elm_json).is_empty());
+
+        let test = get_test_dependencies(&elm_json);
+        assert_eq!(test.len(), 1);
+        assert_eq!(test[0].name, "test");
+        assert!(get_dependencies(&elm_json, Section::TestIndirect).is_empty());
+    }
+
+    #[test]
+    fn test_exposed_modules() {
+        let flat = json!({"exposed-modules": ["Json.Decode", "Json.Encode"]});
+        assert_eq!(
+            get_exposed_modules(&flat),
+            vec!["Json.Decode", "Json.Encode"]
+        );
+
+        let grouped = json!({"exposed-modules": {
+            "Primitives": ["Basics", "String"],
+            "Effects": ["Task"]
+        }});
+        assert_eq!(
+            get_exposed_modules(&grouped),
+            vec!["Basics", "String", "Task"]
+        );
+        assert!(get_exposed_modules(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_package_metadata() {
+        let elm_json = json!({
+            "type": "package",
+            "name": "elm/core",
+            "summary": "Elm's standard libraries",
+            "license": "BSD-3-Clause",
+            "version": "1.0.5",
+            "exposed-modules": {
+                "Primitives": ["Basics", "String"],
+                "Effects": ["Task"]
+            },
+            "elm-version": "0.19.0 <= v < 0.20.0",
+            "dependencies": {"elm/json": "1.0.0 <= v < 2.0.0"},
+            "test-dependencies": {}
+        });
+
+        let metadata = get_package_metadata(&elm_json);
+        assert_eq!(metadata.name.as_deref(), Some("elm/core"));
+        assert_eq!(metadata.license.as_deref(), Some("BSD-3-Clause"));
+        assert_eq!(metadata.exposed_modules, vec!["Basics", "String", "Task"]);
+        let headings: Vec<&str> = metadata
+            .module_groups
+            .iter()
+            .map(|g| g.heading.as_str())
+            .collect();
+        assert_eq!(headings, vec!["Primitives", "Effects"]);
+        assert_eq!(
+            metadata.dependencies.get("elm/json"),
+            Some(&json!("1.0.0 <= v < 2.0.0"))
+        );
+        assert!(metadata.test_dependencies.is_empty());
+
+        let flat = json!({"exposed-modules": ["Json.Decode"]});
+        assert!(get_package_metadata(&flat).module_groups.is_empty());
+    }
+}
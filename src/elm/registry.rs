@@ -0,0 +1,208 @@
+use crate::elm::PackageInfo;
+use std::time::Duration;
+
+pub const DEFAULT_REGISTRY_URL: &str = "https://package.elm-lang.org";
+
+/// Environment variable overriding the package registry base URL, e.g. to
+/// point at a mirror or a local stand-in server
+pub const REGISTRY_URL_ENV: &str = "ELM_PACKAGE_REGISTRY_URL";
+
+/// The registry base URL, without a trailing slash
+pub fn base_url() -> String {
+    std::env::var(REGISTRY_URL_ENV)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_REGISTRY_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .gzip(true)
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Check that `author` and `name` could name a registry package: non-empty
+/// and only ASCII letters, digits, `-` and `_`. Names from tool input end up
+/// in URLs and cache paths, so this keeps out `/`, `..` and the like.
+pub fn validate_package_name(author: &str, name: &str) -> Result<(), String> {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if valid(author) && valid(name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid package name '{author}/{name}': author and name may only contain letters, digits, '-' and '_'"
+        ))
+    }
+}
+
+/// URL of a file published with a package, e.g. `README.md` or `docs.json`
+pub fn package_file_url(base_url: &str, package: &PackageInfo, file: &str) -> String {
+    format!(
+        "{}/packages/{}/{}/{}/{}",
+        base_url.trim_end_matches('/'),
+        package.author,
+        package.name,
+        package.version,
+        file
+    )
+}
+
+/// Fetch a text file from the registry
+pub fn fetch_text(url: &str) -> Result<String, String> {
+    let response = client()?
+        .get(url)
+        .send()
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to fetch {}: HTTP {}",
+            url,
+            response.status()
+        ));
+    }
+
+    response
+        .text()
+        .map_err(|e| format!("Failed to read response from {}: {}", url, e))
+}
+
+/// Fetch a file published with a package from the registry
+pub fn fetch_package_file(
+    base_url: &str,
+    package: &PackageInfo,
+    file: &str,
+) -> Result<String, String> {
+    fetch_text(&package_file_url(base_url, package, file))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// A request received by a stand-in registry
+    pub(crate) struct TestRequest {
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+    }
+
+    impl TestRequest {
+        pub(crate) fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// A response from a stand-in registry: status code, extra headers and body
+    pub(crate) type TestResponse = (u16, Vec<(String, String)>, String);
+
+    /// Start a stand-in registry on localhost that answers every request with
+    /// `handler`. Returns the base URL.
+    pub(crate) fn serve_with<F>(handler: F) -> String
+    where
+        F: Fn(&TestRequest) -> TestResponse + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() <= 2 {
+                        break;
+                    }
+                    if let Some((key, value)) = line.trim_end().split_once(':') {
+                        headers.push((key.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+                let request = TestRequest {
+                    path: request_line
+                        .split_whitespace()
+                        .nth(1)
+                        .unwrap_or("/")
+                        .to_string(),
+                    headers,
+                };
+                let (status, extra_headers, body) = handler(&request);
+                let mut response = format!("HTTP/1.1 {status} Stand-in\r\n");
+                for (key, value) in extra_headers {
+                    response.push_str(&format!("{key}: {value}\r\n"));
+                }
+                response.push_str(&format!(
+                    "Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{address}")
+    }
+
+    /// Start a stand-in registry on localhost serving `routes` (path -> body);
+    /// unknown paths get a 404. Returns the base URL.
+    pub(crate) fn serve(routes: HashMap<String, String>) -> String {
+        serve_with(move |request| match routes.get(&request.path) {
+            Some(body) => (200, vec![], body.clone()),
+            None => (404, vec![], "not found".to_string()),
+        })
+    }
+
+    fn package() -> PackageInfo {
+        PackageInfo {
+            author: "elm".to_string(),
+            name: "core".to_string(),
+            version: "1.0.5".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fetch_package_file() {
+        let base_url = serve(HashMap::from([(
+            "/packages/elm/core/1.0.5/README.md".to_string(),
+            "# Core Libraries".to_string(),
+        )]));
+
+        assert_eq!(
+            fetch_package_file(&base_url, &package(), "README.md").unwrap(),
+            "# Core Libraries"
+        );
+        let missing = fetch_package_file(&base_url, &package(), "docs.json").unwrap_err();
+        assert!(missing.contains("HTTP 404"));
+    }
+
+    #[test]
+    fn test_validate_package_name() {
+        assert!(validate_package_name("elm-community", "list_extra").is_ok());
+        assert!(validate_package_name("elm", "").is_err());
+        assert!(validate_package_name("..", "json").is_err());
+        assert!(validate_package_name("elm", "json/../../x").is_err());
+    }
+
+    #[test]
+    fn test_package_file_url() {
+        assert_eq!(
+            package_file_url("https://example.com/", &package(), "docs.json"),
+            "https://example.com/packages/elm/core/1.0.5/docs.json"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::elm::cache::HttpCache;
+use crate::elm::registry;
+use crate::elm::version::Version;
+use serde::Serialize;
+use serde_json::Value;
+
+/// One published version of a package
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Release {
+    pub version: Version,
+    /// Unix timestamp of when the version was published
+    pub published: i64,
+    /// `published` as an RFC 3339 date
+    pub published_at: Option<String>,
+}
+
+/// How an upgrade from one version to another would be classified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Magnitude {
+    UpToDate,
+    Patch,
+    Minor,
+    Major,
+}
+
+/// How far the project's version of a package trails the latest release
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Behind {
+    /// Releases published after the project's version
+    pub releases: usize,
+    pub magnitude: Magnitude,
+    /// Days between the project's version and the latest being published
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseHistory {
+    pub package: String,
+    /// Every version, newest first
+    pub releases: Vec<Release>,
+    pub latest: Option<Version>,
+    /// The version the project uses, if it uses the package
+    pub current: Option<Version>,
+    /// Set when `current` isn't a published version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_unpublished: Option<bool>,
+    pub behind: Option<Behind>,
+}
+
+/// URL of a package's `releases.json`, which maps each version to the Unix
+/// timestamp it was published at
+pub fn releases_url(base_url: &str, package: &str) -> String {
+    format!(
+        "{}/packages/{}/releases.json",
+        base_url.trim_end_matches('/'),
+        package
+    )
+}
+
+/// Parse `releases.json` into releases, oldest first
+pub fn parse_releases(body: &str) -> Result<Vec<Release>, String> {
+    let json: Value =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse releases.json: {e}"))?;
+    let object = json
+        .as_object()
+        .ok_or_else(|| "releases.json is not an object".to_string())?;
+
+    let mut releases: Vec<Release> = object
+        .iter()
+        .filter_map(|(version, published)| {
+            let published = published.as_i64()?;
+            Some(Release {
+                version: Version::parse(version).ok()?,
+                published,
+                published_at: chrono::DateTime::from_timestamp(published, 0)
+                    .map(|t| t.to_rfc3339()),
+            })
+        })
+        .collect();
+    releases.sort_by_key(|release| release.version);
+    Ok(releases)
+}
+
+/// Fetch the releases of `package` (`author/name`) from the registry at
+/// `base_url`, through `cache`
+pub fn fetch_releases_from(
+    cache: &HttpCache,
+    base_url: &str,
+    package: &str,
+) -> Result<Vec<Release>, String> {
+    let (author, name) = package.split_once('/').unwrap_or((package, ""));
+    registry::validate_package_name(author, name)?;
+    let response = cache
+        .get(
+            &format!("releases/{package}.json"),
+            &releases_url(base_url, package),
+        )
+        .map_err(|e| format!("Failed to fetch releases of {package}: {e}"))?;
+    parse_releases(&response.body)
+}
+
+/// Fetch the releases of `package` from the configured registry, through the
+/// disk cache
+pub fn fetch_releases(package: &str) -> Result<Vec<Release>, String> {
+    fetch_releases_from(&HttpCache::from_env()?, &registry::base_url(), package)
+}
+
+/// The release history of `package`, with `releases` oldest first, compared
+/// with the `current` version the project uses
+pub fn history(package: &str, releases: Vec<Release>, current: Option<Version>) -> ReleaseHistory {
+    let latest = releases.last().cloned();
+    let published = |version: Version| {
+        releases
+            .iter()
+            .find(|r| r.version == version)
+            .map(|r| r.published)
+    };
+
+    let behind = current.zip(latest.as_ref()).map(|(current, latest)| {
+        let magnitude = if latest.version <= current {
+            Magnitude::UpToDate
+        } else if latest.version.major != current.major {
+            Magnitude::Major
+        } else if latest.version.minor != current.minor {
+            Magnitude::Minor
+        } else {
+            Magnitude::Patch
+        };
+        Behind {
+            releases: releases.iter().filter(|r| r.version > current).count(),
+            magnitude,
+            days: published(current).map(|then| ((latest.published - then) / 86_400).max(0)),
+        }
+    });
+
+    ReleaseHistory {
+        package: package.to_string(),
+        latest: latest.map(|r| r.version),
+        current_unpublished: current
+            .filter(|&current| published(current).is_none())
+            .map(|_| true),
+        current,
+        behind,
+        releases: releases.into_iter().rev().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::registry::tests::serve;
+    use crate::elm::test_dir::TestDir;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const RELEASES: &str = r#"{"1.0.0": 1534000000, "1.1.0": 1541000000, "1.1.2": 1560000000, "1.1.3": 1576000000, "oops": 1}"#;
+
+    fn version(input: &str) -> Version {
+        Version::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_history() {
+        let releases = parse_releases(RELEASES).unwrap();
+        let versions: Vec<String> = releases.iter().map(|r| r.version.to_string()).collect();
+        assert_eq!(versions, vec!["1.0.0", "1.1.0", "1.1.2", "1.1.3"]);
+
+        let history = history("elm/json", releases.clone(), Some(version("1.1.0")));
+        assert_eq!(history.latest, Some(version("1.1.3")));
+        assert_eq!(history.releases[0].version, version("1.1.3"));
+        assert_eq!(
+            history.behind,
+            Some(Behind {
+                releases: 2,
+                magnitude: Magnitude::Patch,
+                days: Some(405)
+            })
+        );
+        assert_eq!(history.current_unpublished, None);
+
+        let up_to_date = super::history("elm/json", releases.clone(), Some(version("1.1.3")));
+        assert_eq!(up_to_date.behind.unwrap().magnitude, Magnitude::UpToDate);
+
+        let unpublished = super::history("elm/json", releases.clone(), Some(version("0.9.0")));
+        assert_eq!(unpublished.current_unpublished, Some(true));
+        assert_eq!(unpublished.behind.unwrap().magnitude, Magnitude::Major);
+
+        assert!(super::history("elm/json", releases, None).behind.is_none());
+    }
+
+    #[test]
+    fn test_fetch_releases_from_stand_in_registry() {
+        let base_url = serve(HashMap::from([(
+            "/packages/elm/json/releases.json".to_string(),
+            RELEASES.to_string(),
+        )]));
+        let dir = TestDir::new("releases");
+        let cache = HttpCache::new(dir.to_path_buf(), Duration::from_secs(60));
+
+        let releases = fetch_releases_from(&cache, &base_url, "elm/json").unwrap();
+        assert_eq!(releases.len(), 4);
+        assert!(dir.join("releases/elm/json.json").is_file());
+
+        let missing = fetch_releases_from(&cache, &base_url, "elm/nope").unwrap_err();
+        assert!(missing.contains("elm/nope"), "{missing}");
+
+        let escaping = fetch_releases_from(&cache, &base_url, "../../tmp").unwrap_err();
+        assert!(escaping.contains("Invalid package name"), "{escaping}");
+    }
+}
@@ -1,4 +1,5 @@
-use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use crate::elm::{cache, ranking, registry};
+use nucleo_matcher::pattern::{Atom, AtomKind, CaseMatching, Normalization, Pattern};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -20,81 +21,248 @@ pub struct SearchResult {
     pub score: u32,
 }
 
-const SEARCH_URL: &str = "https://package.elm-lang.org/search.json";
-
-/// Fetch the search index from package.elm-lang.org
-pub fn fetch_search_index() -> Result<Vec<SearchEntry>, String> {
-    let client = reqwest::blocking::Client::builder()
-        .gzip(true)
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+/// The registry's search index, possibly served from the disk cache
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    pub entries: Vec<SearchEntry>,
+    /// Unix timestamp of when the index was last confirmed current
+    pub fetched_at: i64,
+    /// True when the registry was unreachable and an expired cached copy was used
+    pub stale: bool,
+}
 
-    let response = client
-        .get(SEARCH_URL)
-        .send()
+/// Fetch the search index from the package registry, through the disk cache
+pub fn fetch_search_index() -> Result<SearchIndex, String> {
+    let url = format!("{}/search.json", registry::base_url());
+    let response = cache::HttpCache::from_env()?
+        .get("search.json", &url)
         .map_err(|e| format!("Failed to fetch search index: {}", e))?;
 
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to fetch search index: HTTP {}",
-            response.status()
-        ));
+    let entries: Vec<SearchEntry> = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Failed to parse search index: {}", e))?;
+
+    Ok(SearchIndex {
+        entries,
+        fetched_at: response.fetched_at,
+        stale: response.stale,
+    })
+}
+
+/// A package field a query term can be scoped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Author,
+    License,
+    Name,
+    Summary,
+}
+
+impl Field {
+    fn parse(prefix: &str) -> Option<Field> {
+        match prefix.to_lowercase().as_str() {
+            "author" => Some(Field::Author),
+            "license" => Some(Field::License),
+            "name" => Some(Field::Name),
+            "summary" => Some(Field::Summary),
+            _ => None,
+        }
     }
+}
 
-    let entries: Vec<SearchEntry> = response
-        .json()
-        .map_err(|e| format!("Failed to parse search index: {}", e))?;
+/// A `field:value` query term, excluding matches when negated with `-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub field: Field,
+    pub value: String,
+    pub negated: bool,
+}
 
-    Ok(entries)
+/// A search query: free text matched against each package's name and summary,
+/// plus filter terms like `author:elm`, `license:MIT`, `-author:foo`,
+/// `name:json` and `summary:"date format"`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub text: Vec<String>,
+    pub filters: Vec<Filter>,
 }
 
-/// Perform fuzzy search on package name and summary
-/// If exclude_packages is provided, those packages will be filtered out
-pub fn fuzzy_search(
-    query: &str,
+/// Split a query on whitespace, keeping double-quoted phrases together
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+impl SearchQuery {
+    /// Parse a query. Terms with an unknown field, like `Html.Attributes:`,
+    /// are kept as free text.
+    pub fn parse(query: &str) -> SearchQuery {
+        let mut parsed = SearchQuery::default();
+        for token in tokenize(query) {
+            let (negated, term) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+            let filter = term.split_once(':').and_then(|(prefix, value)| {
+                Some(Filter {
+                    field: Field::parse(prefix)?,
+                    value: value.to_string(),
+                    negated,
+                })
+            });
+            match filter {
+                Some(filter) if !filter.value.is_empty() => parsed.filters.push(filter),
+                _ => parsed.text.push(token),
+            }
+        }
+        parsed
+    }
+
+    /// Positive terms scoped to `field`
+    fn terms(&self, field: Field) -> Vec<&str> {
+        self.filters
+            .iter()
+            .filter(|f| f.field == field && !f.negated)
+            .map(|f| f.value.as_str())
+            .collect()
+    }
+}
+
+/// Case-insensitive equality, where a trailing `*` in `pattern` matches any
+/// suffix, e.g. `elm*` for `elm-community`
+fn matches_pattern(value: &str, pattern: &str) -> bool {
+    let (value, pattern) = (value.to_lowercase(), pattern.to_lowercase());
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => value == pattern,
+    }
+}
+
+/// Whether `entry` passes the query's filters. Positive `author:` and
+/// `license:` filters accept any of their values; negated filters reject
+/// entries whose field matches (or, for name and summary, contains) the value.
+fn passes_filters(query: &SearchQuery, entry: &SearchEntry) -> bool {
+    let author = entry.name.split('/').next().unwrap_or("");
+    let any_of = |field: Field, value: &str| {
+        let allowed = query.terms(field);
+        allowed.is_empty() || allowed.iter().any(|p| matches_pattern(value, p))
+    };
+    let excluded = query.filters.iter().filter(|f| f.negated).any(|f| {
+        let contains = |text: &str| text.to_lowercase().contains(&f.value.to_lowercase());
+        match f.field {
+            Field::Author => matches_pattern(author, &f.value),
+            Field::License => matches_pattern(&entry.license, &f.value),
+            Field::Name => contains(&entry.name),
+            Field::Summary => contains(&entry.summary),
+        }
+    });
+    !excluded && any_of(Field::Author, author) && any_of(Field::License, &entry.license)
+}
+
+/// Fuzzy match `terms` against `text`. No terms always matches, scoring 0.
+fn fuzzy_score(terms: &[&str], text: &str, matcher: &mut Matcher) -> Option<u32> {
+    if terms.is_empty() {
+        return Some(0);
+    }
+    let pattern = Pattern::parse(&terms.join(" "), CaseMatching::Ignore, Normalization::Smart);
+    let mut buf = Vec::new();
+    pattern.score(Utf32Str::new(text, &mut buf), matcher)
+}
+
+/// Score `terms` as substrings of `text`, each of which must appear. No terms
+/// always matches, scoring 0.
+fn substring_score(terms: &[&str], text: &str, matcher: &mut Matcher) -> Option<u32> {
+    let mut buf = Vec::new();
+    let haystack = Utf32Str::new(text, &mut buf);
+    terms.iter().try_fold(0, |total, term| {
+        let atom = Atom::new(
+            term,
+            CaseMatching::Ignore,
+            Normalization::Smart,
+            AtomKind::Substring,
+            false,
+        );
+        Some(total + u32::from(atom.score(haystack, matcher)?))
+    })
+}
+
+/// Every entry matching `query`, best first. Free text is fuzzy matched
+/// against `"name summary"`; `name:` and `summary:` terms must appear in that
+/// field as written, ignoring case. Matches are ordered by
+/// `ranking::score`, using `popularity` if given; a query of only filters
+/// ranks packages by author and popularity alone. Packages in
+/// `exclude_packages` are left out.
+pub fn search(
+    query: &SearchQuery,
     entries: &[SearchEntry],
     exclude_packages: Option<&HashSet<String>>,
-    limit: usize,
+    popularity: Option<&ranking::Popularity>,
 ) -> Vec<SearchResult> {
     let mut matcher = Matcher::new(Config::DEFAULT);
-    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+    let text: Vec<&str> = query.text.iter().map(|t| t.as_str()).collect();
+    let name_only = query.terms(Field::Name);
+    let summary_only = query.terms(Field::Summary);
+    let name_terms: Vec<&str> = text.iter().chain(&name_only).copied().collect();
+    let summary_terms: Vec<&str> = text.iter().chain(&summary_only).copied().collect();
 
-    let mut results: Vec<(SearchEntry, u32)> = entries
+    let mut results: Vec<SearchResult> = entries
         .iter()
+        .filter(|entry| exclude_packages.is_none_or(|excluded| !excluded.contains(&entry.name)))
+        .filter(|entry| passes_filters(query, entry))
         .filter_map(|entry| {
-            // Filter out excluded packages if specified
-            if let Some(excluded) = exclude_packages {
-                if excluded.contains(&entry.name) {
-                    return None;
-                }
-            }
-
-            // Create searchable text: "name summary"
             let searchable = format!("{} {}", entry.name, entry.summary);
-            let mut buf = Vec::new();
-            let haystack = Utf32Str::new(&searchable, &mut buf);
-
-            pattern
-                .score(haystack, &mut matcher)
-                .map(|score| (entry.clone(), score))
+            let fuzzy = fuzzy_score(&text, &searchable, &mut matcher)?
+                + substring_score(&name_only, &entry.name, &mut matcher)?
+                + substring_score(&summary_only, &entry.summary, &mut matcher)?;
+            Some(SearchResult {
+                name: entry.name.clone(),
+                summary: entry.summary.clone(),
+                license: entry.license.clone(),
+                version: entry.version.clone(),
+                score: ranking::score(
+                    &entry.name,
+                    &entry.summary,
+                    &name_terms,
+                    &summary_terms,
+                    fuzzy,
+                    popularity,
+                ),
+            })
         })
         .collect();
 
-    // Sort by score descending
-    results.sort_by(|a, b| b.1.cmp(&a.1));
+    // Best score first, then alphabetically
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    results
+}
 
-    // Take top N results and convert to SearchResult
+/// Perform fuzzy search on package name and summary, with the query syntax of
+/// `SearchQuery`. If exclude_packages is provided, those packages will be
+/// filtered out
+pub fn fuzzy_search(
+    query: &str,
+    entries: &[SearchEntry],
+    exclude_packages: Option<&HashSet<String>>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut results = search(&SearchQuery::parse(query), entries, exclude_packages, None);
+    results.truncate(limit);
     results
-        .into_iter()
-        .take(limit)
-        .map(|(entry, score)| SearchResult {
-            name: entry.name,
-            summary: entry.summary,
-            license: entry.license,
-            version: entry.version,
-            score,
-        })
-        .collect()
 }
 
 #[cfg(test)]
@@ -147,4 +315,78 @@ mod tests {
         assert!(!results.is_empty());
         assert_eq!(results[0].name, "NoRedInk/elm-json-decode-pipeline");
     }
+
+    fn entry(name: &str, summary: &str, license: &str) -> SearchEntry {
+        SearchEntry {
+            name: name.to_string(),
+            summary: summary.to_string(),
+            license: license.to_string(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse(
+            r#"date author:elm* -License:GPL-3.0-only summary:"iso 8601" Html.Attributes: -"#,
+        );
+        assert_eq!(query.text, vec!["date", "Html.Attributes:", "-"]);
+        assert_eq!(
+            query.filters,
+            vec![
+                Filter {
+                    field: Field::Author,
+                    value: "elm*".to_string(),
+                    negated: false
+                },
+                Filter {
+                    field: Field::License,
+                    value: "GPL-3.0-only".to_string(),
+                    negated: true
+                },
+                Filter {
+                    field: Field::Summary,
+                    value: "iso 8601".to_string(),
+                    negated: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let entries = vec![
+            entry("elm/json", "Encode and decode JSON values", "BSD-3-Clause"),
+            entry(
+                "elm-community/json-extra",
+                "Convenience functions for working with JSON",
+                "MIT",
+            ),
+            entry("someone/json-fork", "A fork of elm/json", "GPL-3.0-only"),
+            entry(
+                "elm/time",
+                "Work with POSIX times and time zones",
+                "BSD-3-Clause",
+            ),
+        ];
+        let names = |query: &str| -> Vec<String> {
+            search(&SearchQuery::parse(query), &entries, None, None)
+                .into_iter()
+                .map(|r| r.name)
+                .collect()
+        };
+
+        assert_eq!(names("json author:elm"), vec!["elm/json"]);
+        assert_eq!(names("json author:elm author:elm-community").len(), 2);
+        assert_eq!(names("json author:elm*").len(), 2);
+        assert_eq!(names("json -author:someone").len(), 2);
+        assert_eq!(names("json license:mit"), vec!["elm-community/json-extra"]);
+        assert_eq!(names("license:BSD-3-Clause"), vec!["elm/json", "elm/time"]);
+        assert_eq!(
+            names("name:json -name:extra"),
+            vec!["elm/json", "someone/json-fork"]
+        );
+        assert_eq!(names("summary:fork"), vec!["someone/json-fork"]);
+        assert!(names("summary:posix author:someone").is_empty());
+    }
 }
@@ -0,0 +1,180 @@
+use crate::elm::cache::HttpCache;
+use crate::elm::version::Version;
+use crate::elm::{fetcher, registry, PackageInfo};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable pointing at a local registry snapshot directory, used
+/// instead of the live registry when solving dependencies
+pub const SNAPSHOT_DIR_ENV: &str = "ELM_PACKAGE_MCP_REGISTRY_SNAPSHOT";
+
+/// The registry data dependency solving needs: every published version of
+/// every package, and each version's own elm.json
+pub trait PackageRegistry {
+    /// Published versions of `package` (`author/name`)
+    fn versions(&self, package: &str) -> Result<Vec<Version>, String>;
+
+    /// The elm.json published with one version of `package`
+    fn package_elm_json(&self, package: &str, version: &Version) -> Result<Value, String>;
+}
+
+/// Parse the registry's `all-packages` document: a map from `author/name` to
+/// the list of published versions
+fn parse_all_packages(content: &str) -> Result<HashMap<String, Vec<Version>>, String> {
+    let all: HashMap<String, Vec<String>> =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse all-packages: {e}"))?;
+    Ok(all
+        .into_iter()
+        .map(|(name, versions)| {
+            let versions = versions
+                .iter()
+                .filter_map(|v| Version::parse(v).ok())
+                .collect();
+            (name, versions)
+        })
+        .collect())
+}
+
+fn split_name(package: &str, version: &Version) -> Result<PackageInfo, String> {
+    let (author, name) = package
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid package name '{package}', expected 'author/name'"))?;
+    Ok(PackageInfo {
+        author: author.to_string(),
+        name: name.to_string(),
+        version: version.to_string(),
+    })
+}
+
+/// A registry snapshot on disk, laid out like the registry itself:
+///
+/// ```text
+/// <dir>/all-packages.json
+/// <dir>/packages/<author>/<name>/<version>/elm.json
+/// ```
+pub struct SnapshotRegistry {
+    dir: PathBuf,
+    all_packages: HashMap<String, Vec<Version>>,
+}
+
+impl SnapshotRegistry {
+    pub fn load(dir: PathBuf) -> Result<Self, String> {
+        let path = dir.join("all-packages.json");
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Ok(SnapshotRegistry {
+            all_packages: parse_all_packages(&content)?,
+            dir,
+        })
+    }
+
+    /// Every package in the snapshot with its newest version
+    pub fn latest_versions(&self) -> Vec<(String, Version)> {
+        let mut latest: Vec<(String, Version)> = self
+            .all_packages
+            .iter()
+            .filter_map(|(package, versions)| Some((package.clone(), *versions.iter().max()?)))
+            .collect();
+        latest.sort();
+        latest
+    }
+}
+
+impl PackageRegistry for SnapshotRegistry {
+    fn versions(&self, package: &str) -> Result<Vec<Version>, String> {
+        self.all_packages
+            .get(package)
+            .cloned()
+            .ok_or_else(|| format!("Package {package} is not in the registry snapshot"))
+    }
+
+    fn package_elm_json(&self, package: &str, version: &Version) -> Result<Value, String> {
+        let path = self
+            .dir
+            .join("packages")
+            .join(package)
+            .join(version.to_string())
+            .join("elm.json");
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+    }
+}
+
+/// The live registry. The package list goes through the disk cache; package
+/// elm.json files come from the local Elm cache when installed.
+pub struct OnlineRegistry {
+    all_packages: HashMap<String, Vec<Version>>,
+    elm_version: Option<String>,
+}
+
+impl OnlineRegistry {
+    pub fn load(elm_version: Option<String>) -> Result<Self, String> {
+        let url = format!("{}/all-packages", registry::base_url());
+        let response = HttpCache::from_env()?.get("all-packages.json", &url)?;
+        Ok(OnlineRegistry {
+            all_packages: parse_all_packages(&response.body)?,
+            elm_version,
+        })
+    }
+}
+
+impl PackageRegistry for OnlineRegistry {
+    fn versions(&self, package: &str) -> Result<Vec<Version>, String> {
+        self.all_packages
+            .get(package)
+            .cloned()
+            .ok_or_else(|| format!("Package {package} is not in the package registry"))
+    }
+
+    fn package_elm_json(&self, package: &str, version: &Version) -> Result<Value, String> {
+        fetcher::fetch_elm_json(&split_name(package, version)?, self.elm_version.as_deref())
+    }
+}
+
+/// The registry to solve against: the snapshot in `dir`, or the one named by
+/// `$ELM_PACKAGE_MCP_REGISTRY_SNAPSHOT`, or else the live registry
+pub fn load_registry(
+    dir: Option<PathBuf>,
+    elm_version: Option<String>,
+) -> Result<Box<dyn PackageRegistry + Send>, String> {
+    let dir = dir.or_else(|| {
+        std::env::var_os(SNAPSHOT_DIR_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    });
+    match dir {
+        Some(dir) => Ok(Box::new(SnapshotRegistry::load(dir)?)),
+        None => Ok(Box::new(OnlineRegistry::load(elm_version)?)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// The registry snapshot fixture in `tests/fixtures/registry`
+    pub(crate) fn fixture_registry() -> SnapshotRegistry {
+        SnapshotRegistry::load(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/registry"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_registry() {
+        let registry = fixture_registry();
+        let versions = registry.versions("elm/json").unwrap();
+        assert!(versions.contains(&Version::parse("1.1.3").unwrap()));
+        assert!(registry.versions("nobody/nothing").is_err());
+
+        let elm_json = registry
+            .package_elm_json("elm/json", &Version::parse("1.1.3").unwrap())
+            .unwrap();
+        assert_eq!(elm_json["name"], "elm/json");
+    }
+}
@@ -0,0 +1,657 @@
+use crate::elm::home::DEFAULT_ELM_VERSION;
+use crate::elm::reader::{self, ProjectType, Section};
+use crate::elm::snapshot::PackageRegistry;
+use crate::elm::version::{Constraint, Version};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::rc::Rc;
+
+/// Give up on a solve after trying this many package versions
+const MAX_STEPS: usize = 100_000;
+
+/// A constraint on a package and who imposed it
+#[derive(Debug, Clone)]
+struct Requirement {
+    package: String,
+    constraint: Constraint,
+    required_by: String,
+}
+
+type Solution = BTreeMap<String, Version>;
+
+/// Dependencies of each package version read so far
+type DependencyCache = HashMap<(String, Version), Rc<Vec<Requirement>>>;
+
+/// A backtracking solver over a package registry, trying the newest allowed
+/// version of each package first, like the Elm compiler's
+struct Solver<'a> {
+    registry: &'a dyn PackageRegistry,
+    elm_version: Version,
+    dependencies: RefCell<DependencyCache>,
+    steps: Cell<usize>,
+    /// The most recent reason a branch failed, reported if nothing succeeds
+    conflict: RefCell<Option<String>>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(registry: &'a dyn PackageRegistry, elm_version: Version) -> Self {
+        Solver {
+            registry,
+            elm_version,
+            dependencies: RefCell::new(HashMap::new()),
+            steps: Cell::new(0),
+            conflict: RefCell::new(None),
+        }
+    }
+
+    fn solve(&self, requirements: Vec<Requirement>) -> Option<Solution> {
+        self.search(requirements.into(), Solution::new(), HashMap::new())
+    }
+
+    /// The dependencies of one package version, or why it can't be used
+    fn dependencies_of(
+        &self,
+        package: &str,
+        version: Version,
+    ) -> Result<Rc<Vec<Requirement>>, String> {
+        let key = (package.to_string(), version);
+        if let Some(cached) = self.dependencies.borrow().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let elm_json = self.registry.package_elm_json(package, &version)?;
+        if let Some(range) = reader::get_elm_version(&elm_json) {
+            let supported = Constraint::parse(&range)
+                .map(|c| c.contains(&self.elm_version))
+                .unwrap_or(false);
+            if !supported {
+                return Err(format!(
+                    "{package} {version} requires Elm {range}, not {}",
+                    self.elm_version
+                ));
+            }
+        }
+
+        let required_by = format!("{package} {version}");
+        let requirements: Rc<Vec<Requirement>> = Rc::new(
+            reader::get_direct_dependencies(&elm_json)
+                .into_iter()
+                .map(|dependency| Requirement {
+                    package: format!("{}/{}", dependency.author, dependency.name),
+                    constraint: dependency.constraint,
+                    required_by: required_by.clone(),
+                })
+                .collect(),
+        );
+        self.dependencies
+            .borrow_mut()
+            .insert(key, requirements.clone());
+        Ok(requirements)
+    }
+
+    fn fail(&self, reason: String) {
+        *self.conflict.borrow_mut() = Some(reason);
+    }
+
+    fn explain(package: &str, requirements: &[Requirement]) -> String {
+        let constraints: Vec<String> = requirements
+            .iter()
+            .map(|r| format!("{} (from {})", r.constraint, r.required_by))
+            .collect();
+        format!(
+            "No version of {package} satisfies all of: {}",
+            constraints.join(", ")
+        )
+    }
+
+    fn search(
+        &self,
+        mut pending: VecDeque<Requirement>,
+        chosen: Solution,
+        mut required: HashMap<String, Vec<Requirement>>,
+    ) -> Option<Solution> {
+        let Some(next) = pending.pop_front() else {
+            return Some(chosen);
+        };
+
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get() > MAX_STEPS {
+            self.fail(format!(
+                "Gave up after trying {MAX_STEPS} package versions without finding a solution"
+            ));
+            return None;
+        }
+
+        let requirements = required.entry(next.package.clone()).or_default();
+        requirements.push(next.clone());
+        if let Some(version) = chosen.get(&next.package) {
+            if next.constraint.contains(version) {
+                return self.search(pending, chosen, required);
+            }
+            self.fail(Self::explain(&next.package, requirements));
+            return None;
+        }
+
+        let versions = match self.registry.versions(&next.package) {
+            Ok(versions) => versions,
+            Err(e) => {
+                self.fail(e);
+                return None;
+            }
+        };
+        let mut candidates: Vec<Version> = versions
+            .into_iter()
+            .filter(|v| requirements.iter().all(|r| r.constraint.contains(v)))
+            .collect();
+        if candidates.is_empty() {
+            self.fail(Self::explain(&next.package, requirements));
+            return None;
+        }
+        candidates.sort_by(|a, b| b.cmp(a));
+
+        for version in candidates {
+            let dependencies = match self.dependencies_of(&next.package, version) {
+                Ok(dependencies) => dependencies,
+                Err(e) => {
+                    self.fail(e);
+                    continue;
+                }
+            };
+            let mut pending = pending.clone();
+            pending.extend(dependencies.iter().cloned());
+            let mut chosen = chosen.clone();
+            chosen.insert(next.package.clone(), version);
+            if let Some(solution) = self.search(pending, chosen, required.clone()) {
+                return Some(solution);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlanStatus {
+    /// Already a direct dependency; nothing to do
+    AlreadyInstalled,
+    /// Already in elm.json as an indirect or test dependency; it moves to
+    /// direct dependencies at the same version, and a test dependency's own
+    /// dependencies move to indirect dependencies
+    Promote,
+    /// New packages are added or versions change
+    Install,
+    /// No set of versions satisfies every constraint
+    Conflict,
+}
+
+/// One elm.json entry that `elm install` would add, change or remove
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub package: String,
+    #[serde(rename = "type")]
+    pub section: Section,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallPlan {
+    pub package: String,
+    pub status: PlanStatus,
+    /// The version that would be installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+    pub changes: Vec<Change>,
+    /// The resulting elm.json
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elm_json: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<String>,
+}
+
+/// elm.json dependencies as `author/name` -> constraint, per section
+type Sections = BTreeMap<Section, BTreeMap<String, Constraint>>;
+
+fn read_sections(elm_json: &Value) -> Sections {
+    Section::ALL
+        .into_iter()
+        .map(|section| {
+            let dependencies = reader::get_dependencies(elm_json, section)
+                .into_iter()
+                .map(|d| (format!("{}/{}", d.author, d.name), d.constraint))
+                .collect();
+            (section, dependencies)
+        })
+        .collect()
+}
+
+/// Work out what `elm install <package>` would do to `elm_json`, solving
+/// against `registry`. `version` pins the package instead of taking the
+/// newest compatible release.
+///
+/// Applications follow the compiler's strategy: keep every pinned version if
+/// possible, then let indirect dependencies move, then allow direct
+/// dependencies to take patch, minor and finally any upgrade.
+pub fn plan_install(
+    elm_json: &Value,
+    package: &str,
+    version: Option<Version>,
+    registry: &dyn PackageRegistry,
+) -> Result<InstallPlan, String> {
+    if !package.contains('/') {
+        return Err(format!(
+            "Invalid package name '{package}', expected 'author/name'"
+        ));
+    }
+
+    let project_type = reader::get_project_type(elm_json);
+    let sections = read_sections(elm_json);
+    let mut plan = InstallPlan {
+        package: package.to_string(),
+        status: PlanStatus::Install,
+        version: None,
+        changes: vec![],
+        elm_json: None,
+        conflict: None,
+    };
+
+    if let Some(existing) = sections[&Section::Direct].get(package) {
+        plan.status = PlanStatus::AlreadyInstalled;
+        plan.version = existing.is_exact().then_some(existing.lower);
+        return Ok(plan);
+    }
+
+    let wanted = version.map(Constraint::exact);
+    let listed = Section::ALL
+        .into_iter()
+        .find_map(|section| Some((section, sections[&section].get(package)?.clone())));
+    if let (ProjectType::Application, Some((section, existing))) = (project_type, &listed) {
+        if wanted.is_none() || wanted.as_ref() == Some(existing) {
+            let mut updated = sections.clone();
+            for dependencies in updated.values_mut() {
+                dependencies.remove(package);
+            }
+            updated
+                .get_mut(&Section::Direct)
+                .unwrap()
+                .insert(package.to_string(), existing.clone());
+            // A package promoted out of the test dependencies brings its own
+            // dependencies along, or elm.json would list them only for tests
+            if matches!(section, Section::TestDirect | Section::TestIndirect) {
+                for name in dependency_closure(package, existing.lower, &sections, registry)? {
+                    for test_section in [Section::TestDirect, Section::TestIndirect] {
+                        if let Some(constraint) =
+                            updated.get_mut(&test_section).unwrap().remove(&name)
+                        {
+                            updated
+                                .get_mut(&Section::Indirect)
+                                .unwrap()
+                                .insert(name.clone(), constraint);
+                        }
+                    }
+                }
+            }
+            plan.status = PlanStatus::Promote;
+            plan.version = Some(existing.lower);
+            plan.changes = changes(&sections, &updated);
+            plan.elm_json = Some(write_sections(elm_json, project_type, &updated));
+            return Ok(plan);
+        }
+    }
+
+    let elm_version = match project_type {
+        ProjectType::Application => reader::get_elm_version(elm_json),
+        ProjectType::Package => None,
+    }
+    .and_then(|v| Version::parse(&v).ok())
+    .unwrap_or_else(|| Version::parse(DEFAULT_ELM_VERSION).unwrap());
+    let solver = Solver::new(registry, elm_version);
+
+    let requirement = |package: &str, constraint: Constraint, required_by: &str| Requirement {
+        package: package.to_string(),
+        constraint,
+        required_by: required_by.to_string(),
+    };
+    let new_requirement = requirement(
+        package,
+        wanted.clone().unwrap_or_else(Constraint::anything),
+        "the package being installed",
+    );
+
+    let updated = match project_type {
+        ProjectType::Application => {
+            let direct = &sections[&Section::Direct];
+            let indirect = &sections[&Section::Indirect];
+            let pinned = |deps: &BTreeMap<String, Constraint>, relax: fn(Version) -> Constraint| {
+                deps.iter()
+                    .map(|(name, c)| requirement(name, relax(c.lower), "elm.json"))
+                    .collect::<Vec<_>>()
+            };
+            let attempts: Vec<Vec<Requirement>> = vec![
+                [
+                    pinned(direct, Constraint::exact),
+                    pinned(indirect, Constraint::exact),
+                ]
+                .concat(),
+                pinned(direct, Constraint::exact),
+                pinned(direct, Constraint::until_next_minor),
+                pinned(direct, Constraint::until_next_major),
+                pinned(direct, |_| Constraint::anything()),
+            ];
+
+            let solution = attempts.into_iter().find_map(|mut requirements| {
+                requirements.push(new_requirement.clone());
+                solver.solve(requirements)
+            });
+            solution.map(|solution| {
+                let mut updated = sections.clone();
+                let new_direct: BTreeMap<String, Constraint> = direct
+                    .keys()
+                    .chain(std::iter::once(&package.to_string()))
+                    .filter_map(|name| {
+                        Some((name.clone(), Constraint::exact(*solution.get(name)?)))
+                    })
+                    .collect();
+                let new_indirect: BTreeMap<String, Constraint> = solution
+                    .iter()
+                    .filter(|(name, _)| !new_direct.contains_key(*name))
+                    .map(|(name, v)| (name.clone(), Constraint::exact(*v)))
+                    .collect();
+                plan.version = solution.get(package).copied();
+                for section in [Section::TestDirect, Section::TestIndirect] {
+                    updated
+                        .get_mut(&section)
+                        .unwrap()
+                        .retain(|name, _| !solution.contains_key(name));
+                }
+                updated.insert(Section::Direct, new_direct);
+                updated.insert(Section::Indirect, new_indirect);
+                updated
+            })
+        }
+        ProjectType::Package => {
+            let mut requirements: Vec<Requirement> = sections[&Section::Direct]
+                .iter()
+                .map(|(name, c)| requirement(name, c.clone(), "elm.json"))
+                .collect();
+            requirements.push(new_requirement);
+            solver.solve(requirements).map(|solution| {
+                let mut updated = sections.clone();
+                let chosen = solution[package];
+                plan.version = Some(chosen);
+                updated
+                    .get_mut(&Section::TestDirect)
+                    .unwrap()
+                    .remove(package);
+                updated
+                    .get_mut(&Section::Direct)
+                    .unwrap()
+                    .insert(package.to_string(), Constraint::until_next_major(chosen));
+                updated
+            })
+        }
+    };
+
+    match updated {
+        Some(updated) => {
+            plan.changes = changes(&sections, &updated);
+            plan.elm_json = Some(write_sections(elm_json, project_type, &updated));
+        }
+        None => {
+            plan.status = PlanStatus::Conflict;
+            plan.conflict = Some(
+                solver
+                    .conflict
+                    .borrow()
+                    .clone()
+                    .unwrap_or_else(|| format!("No compatible version of {package} found")),
+            );
+        }
+    }
+    Ok(plan)
+}
+
+/// Every package `package` at `version` depends on, directly or not, at the
+/// versions elm.json pins. Dependencies missing from elm.json are skipped.
+fn dependency_closure(
+    package: &str,
+    version: Version,
+    sections: &Sections,
+    registry: &dyn PackageRegistry,
+) -> Result<BTreeSet<String>, String> {
+    let pinned = |name: &str| {
+        sections
+            .values()
+            .find_map(|dependencies| dependencies.get(name))
+            .map(|c| c.lower)
+    };
+    let mut closure = BTreeSet::new();
+    let mut queue = VecDeque::from([(package.to_string(), version)]);
+    while let Some((name, version)) = queue.pop_front() {
+        let elm_json = registry.package_elm_json(&name, &version)?;
+        for dependency in reader::get_direct_dependencies(&elm_json) {
+            let dependency = format!("{}/{}", dependency.author, dependency.name);
+            if let Some(version) = pinned(&dependency) {
+                if closure.insert(dependency.clone()) {
+                    queue.push_back((dependency, version));
+                }
+            }
+        }
+    }
+    Ok(closure)
+}
+
+fn changes(before: &Sections, after: &Sections) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for section in Section::ALL {
+        let (old, new) = (&before[&section], &after[&section]);
+        let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for name in names {
+            let (from, to) = (old.get(name), new.get(name));
+            if from != to {
+                changes.push(Change {
+                    package: name.clone(),
+                    section,
+                    from: from.map(|c| c.to_string()),
+                    to: to.map(|c| c.to_string()),
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// `elm_json` with its dependency sections replaced, keys sorted like the
+/// compiler writes them
+fn write_sections(elm_json: &Value, project_type: ProjectType, sections: &Sections) -> Value {
+    let map = |section: Section| -> Value {
+        Value::Object(
+            sections[&section]
+                .iter()
+                .map(|(name, c)| (name.clone(), Value::String(c.to_string())))
+                .collect::<Map<String, Value>>(),
+        )
+    };
+    let (dependencies, test_dependencies) = match project_type {
+        ProjectType::Application => (
+            serde_json::json!({"direct": map(Section::Direct), "indirect": map(Section::Indirect)}),
+            serde_json::json!({"direct": map(Section::TestDirect), "indirect": map(Section::TestIndirect)}),
+        ),
+        ProjectType::Package => (map(Section::Direct), map(Section::TestDirect)),
+    };
+
+    let mut updated = elm_json.clone();
+    updated["dependencies"] = dependencies;
+    updated["test-dependencies"] = test_dependencies;
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::snapshot::tests::fixture_registry;
+    use serde_json::json;
+
+    fn application(json_version: &str) -> Value {
+        json!({
+            "type": "application",
+            "source-directories": ["src"],
+            "elm-version": "0.19.1",
+            "dependencies": {
+                "direct": {
+                    "elm/browser": "1.0.2",
+                    "elm/core": "1.0.5",
+                    "elm/html": "1.0.0"
+                },
+                "indirect": {
+                    "elm/json": json_version,
+                    "elm/time": "1.0.0",
+                    "elm/url": "1.0.0",
+                    "elm/virtual-dom": "1.0.3"
+                }
+            },
+            "test-dependencies": {"direct": {}, "indirect": {}}
+        })
+    }
+
+    fn plan(elm_json: &Value, package: &str, version: Option<&str>) -> InstallPlan {
+        let version = version.map(|v| Version::parse(v).unwrap());
+        plan_install(elm_json, package, version, &fixture_registry()).unwrap()
+    }
+
+    fn summary(plan: &InstallPlan) -> Vec<String> {
+        plan.changes
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} {}: {} -> {}",
+                    serde_json::to_value(c.section).unwrap().as_str().unwrap(),
+                    c.package,
+                    c.from.as_deref().unwrap_or("none"),
+                    c.to.as_deref().unwrap_or("none")
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_install_new_package() {
+        let result = plan(&application("1.1.3"), "elm/http", None);
+        assert_eq!(result.status, PlanStatus::Install);
+        assert_eq!(result.version, Version::parse("2.0.0").ok());
+        assert_eq!(
+            summary(&result),
+            vec![
+                "direct elm/http: none -> 2.0.0",
+                "indirect elm/bytes: none -> 1.0.8",
+                "indirect elm/file: none -> 1.0.5",
+            ]
+        );
+        let elm_json = result.elm_json.unwrap();
+        assert_eq!(elm_json["dependencies"]["direct"]["elm/http"], "2.0.0");
+        assert_eq!(elm_json["source-directories"], json!(["src"]));
+
+        let pinned = plan(&application("1.1.3"), "elm/http", Some("1.0.0"));
+        assert_eq!(summary(&pinned), vec!["direct elm/http: none -> 1.0.0"]);
+    }
+
+    #[test]
+    fn test_existing_packages() {
+        let direct = plan(&application("1.1.3"), "elm/core", None);
+        assert_eq!(direct.status, PlanStatus::AlreadyInstalled);
+        assert!(direct.changes.is_empty());
+
+        let indirect = plan(&application("1.1.3"), "elm/json", None);
+        assert_eq!(indirect.status, PlanStatus::Promote);
+        assert_eq!(
+            summary(&indirect),
+            vec![
+                "direct elm/json: none -> 1.1.3",
+                "indirect elm/json: 1.1.3 -> none"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_promote_test_dependency_moves_its_dependencies() {
+        let mut elm_json = application("1.1.3");
+        elm_json["test-dependencies"] = json!({
+            "direct": {"elm/http": "2.0.0"},
+            "indirect": {"elm/bytes": "1.0.8", "elm/file": "1.0.5"}
+        });
+        let result = plan(&elm_json, "elm/http", None);
+        assert_eq!(result.status, PlanStatus::Promote);
+        assert_eq!(
+            summary(&result),
+            vec![
+                "direct elm/http: none -> 2.0.0",
+                "indirect elm/bytes: none -> 1.0.8",
+                "indirect elm/file: none -> 1.0.5",
+                "test-direct elm/http: 2.0.0 -> none",
+                "test-indirect elm/bytes: 1.0.8 -> none",
+                "test-indirect elm/file: 1.0.5 -> none",
+            ]
+        );
+        assert_eq!(
+            result.elm_json.unwrap()["test-dependencies"],
+            json!({"direct": {}, "indirect": {}})
+        );
+    }
+
+    #[test]
+    fn test_install_upgrades_indirect() {
+        let result = plan(&application("1.1.2"), "new/needs-json-113", None);
+        assert_eq!(result.status, PlanStatus::Install);
+        assert_eq!(
+            summary(&result),
+            vec![
+                "direct new/needs-json-113: none -> 1.0.0",
+                "indirect elm/json: 1.1.2 -> 1.1.3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let result = plan(&application("1.1.3"), "legacy/old-json", None);
+        assert_eq!(result.status, PlanStatus::Conflict);
+        let conflict = result.conflict.unwrap();
+        assert!(conflict.contains("elm/json"), "{conflict}");
+        assert!(conflict.contains("legacy/old-json 1.0.0"), "{conflict}");
+        assert!(result.elm_json.is_none());
+
+        let result = plan(&application("1.1.3"), "future/needs-core-2", None);
+        assert_eq!(result.status, PlanStatus::Conflict);
+        assert!(result.conflict.unwrap().contains("2.0.0 <= v < 3.0.0"));
+
+        let result = plan(&application("1.1.3"), "old/elm-18", None);
+        assert_eq!(result.status, PlanStatus::Conflict);
+        assert!(result.conflict.unwrap().contains("requires Elm"));
+
+        let result = plan(&application("1.1.3"), "nobody/nothing", None);
+        assert_eq!(result.status, PlanStatus::Conflict);
+    }
+
+    #[test]
+    fn test_install_into_package() {
+        let elm_json = json!({
+            "type": "package",
+            "name": "me/thing",
+            "elm-version": "0.19.0 <= v < 0.20.0",
+            "dependencies": {"elm/core": "1.0.0 <= v < 2.0.0"},
+            "test-dependencies": {}
+        });
+        let result = plan(&elm_json, "elm/json", None);
+        assert_eq!(result.status, PlanStatus::Install);
+        assert_eq!(
+            summary(&result),
+            vec!["direct elm/json: none -> 1.1.3 <= v < 2.0.0"]
+        );
+        assert_eq!(
+            result.elm_json.unwrap()["dependencies"],
+            json!({"elm/core": "1.0.0 <= v < 2.0.0", "elm/json": "1.1.3 <= v < 2.0.0"})
+        );
+    }
+}
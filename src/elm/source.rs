@@ -0,0 +1,237 @@
+use crate::elm::reader::{self, ProjectType};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One `.elm` file from the project's source directories
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    /// Path relative to the project directory
+    pub path: String,
+    /// Module name derived from the path, e.g. `Page/Home.elm` -> `Page.Home`
+    pub module: String,
+    /// Modules named in `import` lines, in order
+    pub imports: Vec<String>,
+}
+
+/// The source directories of a project, resolved against the directory
+/// holding its elm.json. Packages always keep their code in `src`.
+pub fn source_directories(elm_json: &Value, project_dir: &Path) -> Vec<PathBuf> {
+    match reader::get_project_type(elm_json) {
+        ProjectType::Package => vec![project_dir.join("src")],
+        ProjectType::Application => elm_json
+            .get("source-directories")
+            .and_then(|dirs| dirs.as_array())
+            .map(|dirs| {
+                dirs.iter()
+                    .filter_map(|dir| dir.as_str())
+                    .map(|dir| project_dir.join(dir))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Every `.elm` file under `dir`, sorted, skipping hidden directories and
+/// `elm-stuff`
+fn elm_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if !file_name.starts_with('.') && file_name != "elm-stuff" {
+                elm_files(&path, files)?;
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("elm") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The module name a file in `source_dir` must declare
+fn module_name(source_dir: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(source_dir).ok()?.with_extension("");
+    let parts: Option<Vec<&str>> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect();
+    Some(parts?.join("."))
+}
+
+/// `source` with `{- -}` block comments (which nest) and `--` line comments
+/// removed. String literals are kept as they are, so a `--` inside one is
+/// treated as a comment; that can't hide an import, since imports come
+/// before any declarations.
+pub fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('-')) => {
+                chars.next();
+                depth += 1;
+            }
+            ('-', Some('}')) if depth > 0 => {
+                chars.next();
+                depth -= 1;
+            }
+            ('-', Some('-')) if depth == 0 => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            // Keep line breaks inside comments so line positions still match
+            ('\n', _) => out.push('\n'),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The modules imported by an Elm source file. Imports start at the beginning
+/// of a line: `import Html.Attributes as Attr exposing (..)`.
+pub fn parse_imports(source: &str) -> Vec<String> {
+    strip_comments(source)
+        .lines()
+        .filter_map(|line| line.strip_prefix("import "))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .map(|module| module.to_string())
+        .collect()
+}
+
+/// An `.elm` file found in the project's source directories
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectFile {
+    /// Path relative to the project directory
+    pub path: String,
+    /// Module name derived from the path
+    pub module: String,
+    pub file: PathBuf,
+}
+
+/// Every `.elm` file in the project's source directories. Directories that
+/// don't exist are skipped, as the compiler would report them anyway.
+pub fn project_files(elm_json: &Value, project_dir: &Path) -> Result<Vec<ProjectFile>, String> {
+    let mut project_files = Vec::new();
+    for dir in source_directories(elm_json, project_dir) {
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut files = Vec::new();
+        elm_files(&dir, &mut files)?;
+        for file in files {
+            let path = file.strip_prefix(project_dir).unwrap_or(&file);
+            project_files.push(ProjectFile {
+                path: path.to_string_lossy().to_string(),
+                module: module_name(&dir, &file).unwrap_or_default(),
+                file,
+            });
+        }
+    }
+    Ok(project_files)
+}
+
+/// Read the imports of every `.elm` file in the project's source directories
+pub fn scan_project(elm_json: &Value, project_dir: &Path) -> Result<Vec<SourceFile>, String> {
+    project_files(elm_json, project_dir)?
+        .into_iter()
+        .map(|project_file| {
+            let content = fs::read_to_string(&project_file.file)
+                .map_err(|e| format!("Failed to read {}: {e}", project_file.file.display()))?;
+            Ok(SourceFile {
+                path: project_file.path,
+                module: project_file.module,
+                imports: parse_imports(&content),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::test_dir::TestDir;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_imports() {
+        let source = r#"module Main exposing (main)
+
+{-| The app.
+
+import NotReally
+
+-}
+
+import Browser
+import Html exposing (Html, div)
+import Html.Attributes as Attr
+-- import Commented.Out
+{- {- nested -} import AlsoCommented -}
+import Json.Decode as D exposing
+    ( Decoder
+    )
+import Page.Home -- trailing comment
+
+main = div [] []
+"#;
+        assert_eq!(
+            parse_imports(source),
+            vec![
+                "Browser",
+                "Html",
+                "Html.Attributes",
+                "Json.Decode",
+                "Page.Home"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_project() {
+        let dir = TestDir::new("source");
+        fs::create_dir_all(dir.join("src/Page")).unwrap();
+        fs::create_dir_all(dir.join("src/elm-stuff")).unwrap();
+        fs::write(
+            dir.join("src/Main.elm"),
+            "module Main exposing (..)\nimport Html\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/Page/Home.elm"),
+            "module Page.Home exposing (..)\nimport Json.Decode\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/elm-stuff/Generated.elm"), "import Ignored\n").unwrap();
+        fs::write(dir.join("src/notes.txt"), "import Ignored\n").unwrap();
+
+        let elm_json = json!({"type": "application", "source-directories": ["src", "missing"]});
+        let files = scan_project(&elm_json, &dir).unwrap();
+
+        let summary: Vec<(&str, &str, Vec<String>)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.module.as_str(), f.imports.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/Main.elm", "Main", vec!["Html".to_string()]),
+                (
+                    "src/Page/Home.elm",
+                    "Page.Home",
+                    vec!["Json.Decode".to_string()]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A scratch directory for a test, emptied when created and deleted when
+/// dropped, so it's cleaned up even when an assertion fails
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// `elm-package-mcp-server-test-<pid>-<name>` in the system temp
+    /// directory. `name` must be unique among tests.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "elm-package-mcp-server-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,465 @@
+use crate::elm::annotation::{constraint, same_constructor, unqualified, Type};
+use crate::elm::fetcher::Module;
+use crate::elm::PackageInfo;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Arguments beyond this count are only matched in their declared order,
+/// since trying every permutation grows factorially
+const MAX_PERMUTED_ARGS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeMatch {
+    pub package: String,
+    pub version: String,
+    pub module: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_annotation: String,
+    pub distance: u32,
+}
+
+/// Find every value, binop and union constructor in `modules` whose type
+/// matches `query`, scored by [`distance`]
+pub fn search_modules(query: &Type, package: &PackageInfo, modules: &[Module]) -> Vec<TypeMatch> {
+    let mut matches = Vec::new();
+    let mut push = |module: &str, name: String, candidate: &Type| {
+        if let Some(distance) = distance(query, candidate) {
+            matches.push(TypeMatch {
+                package: format!("{}/{}", package.author, package.name),
+                version: package.version.clone(),
+                module: module.to_string(),
+                name,
+                type_annotation: candidate.to_string(),
+                distance,
+            });
+        }
+    };
+
+    for module in modules {
+        for value in &module.values {
+            if let Some(candidate) = &value.parsed_type {
+                push(&module.name, value.name.clone(), candidate);
+            }
+        }
+        for binop in &module.binops {
+            if let Some(candidate) = &binop.parsed_type {
+                push(&module.name, format!("({})", binop.name), candidate);
+            }
+        }
+        for union in &module.unions {
+            let union_type = Type::Constructor {
+                name: format!("{}.{}", module.name, union.name),
+                args: union
+                    .args
+                    .iter()
+                    .map(|name| Type::Var { name: name.clone() })
+                    .collect(),
+            };
+            for case in &union.parsed_cases {
+                let candidate =
+                    case.args
+                        .iter()
+                        .rev()
+                        .fold(union_type.clone(), |acc, arg| Type::Lambda {
+                            arg: Box::new(arg.clone()),
+                            result: Box::new(acc),
+                        });
+                push(&module.name, case.name.clone(), &candidate);
+            }
+        }
+    }
+
+    matches
+}
+
+/// How far `candidate` is from `query`, or `None` if they cannot be unified.
+///
+/// Zero means the types are identical up to renaming of type variables and
+/// module qualification. Instantiating a type variable, matching a more
+/// specific candidate, extra record fields and reordered arguments each add
+/// to the distance.
+///
+/// Record matching is deliberately looser than Elm's: a closed candidate
+/// record with fields the query lacks, like `{ x : Int, y : Int }` for a
+/// query `{ x : Int }`, wouldn't type-check, but it's kept as a near miss one
+/// step away per extra field, since a query often leaves out fields the
+/// searcher didn't think of. A record naming a field twice matches nothing.
+pub fn distance(query: &Type, candidate: &Type) -> Option<u32> {
+    let (query_args, query_result) = query.flatten_lambda();
+    let (candidate_args, candidate_result) = candidate.flatten_lambda();
+    if query_args.len() != candidate_args.len() {
+        return None;
+    }
+
+    let orders = if candidate_args.len() <= MAX_PERMUTED_ARGS {
+        permutations(candidate_args.len())
+    } else {
+        vec![(0..candidate_args.len()).collect()]
+    };
+
+    orders
+        .into_iter()
+        .filter_map(|order| {
+            let mut unifier = Unifier::default();
+            let reordered = order.iter().enumerate().filter(|(i, j)| i != *j).count();
+            for (query_arg, &j) in query_args.iter().zip(&order) {
+                if !unifier.unify(query_arg, candidate_args[j]) {
+                    return None;
+                }
+            }
+            if !unifier.unify(query_result, candidate_result) {
+                return None;
+            }
+            Some(unifier.penalty + reordered as u32)
+        })
+        .min()
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for rest in permutations(n - 1) {
+        for position in 0..=rest.len() {
+            let mut order = rest.clone();
+            order.insert(position, n - 1);
+            result.push(order);
+        }
+    }
+    result
+}
+
+#[derive(Default)]
+struct Unifier {
+    /// Consistent renaming between query and candidate type variables
+    query_to_candidate: HashMap<String, String>,
+    candidate_to_query: HashMap<String, String>,
+    /// Candidate variables instantiated with a concrete query type
+    candidate_bindings: HashMap<String, Type>,
+    /// Query variables matched by a concrete candidate type
+    query_bindings: HashMap<String, Type>,
+    penalty: u32,
+}
+
+impl Unifier {
+    fn unify(&mut self, query: &Type, candidate: &Type) -> bool {
+        match (query, candidate) {
+            (Type::Var { name: q }, Type::Var { name: c }) => {
+                match (
+                    self.query_to_candidate.get(q),
+                    self.candidate_to_query.get(c),
+                ) {
+                    (Some(existing), _) => existing == c,
+                    (_, Some(existing)) => existing == q,
+                    (None, None) => {
+                        if self.query_bindings.contains_key(q)
+                            || self.candidate_bindings.contains_key(c)
+                        {
+                            return false;
+                        }
+                        if constraint(q) != constraint(c) {
+                            self.penalty += 1;
+                        }
+                        self.query_to_candidate.insert(q.clone(), c.clone());
+                        self.candidate_to_query.insert(c.clone(), q.clone());
+                        true
+                    }
+                }
+            }
+            (_, Type::Var { name: c }) => {
+                if self.candidate_to_query.contains_key(c) {
+                    return false;
+                }
+                if let Some(bound) = self.candidate_bindings.get(c) {
+                    return equivalent(bound, query);
+                }
+                if !satisfies_constraint(c, query) {
+                    return false;
+                }
+                self.candidate_bindings.insert(c.clone(), query.clone());
+                self.penalty += 1;
+                true
+            }
+            (Type::Var { name: q }, _) => {
+                if self.query_to_candidate.contains_key(q) {
+                    return false;
+                }
+                if let Some(bound) = self.query_bindings.get(q) {
+                    return equivalent(bound, candidate);
+                }
+                self.query_bindings.insert(q.clone(), candidate.clone());
+                self.penalty += 2;
+                true
+            }
+            (
+                Type::Lambda {
+                    arg: query_arg,
+                    result: query_result,
+                },
+                Type::Lambda {
+                    arg: candidate_arg,
+                    result: candidate_result,
+                },
+            ) => self.unify(query_arg, candidate_arg) && self.unify(query_result, candidate_result),
+            (
+                Type::Tuple { items: query_items },
+                Type::Tuple {
+                    items: candidate_items,
+                },
+            ) => {
+                query_items.len() == candidate_items.len()
+                    && query_items
+                        .iter()
+                        .zip(candidate_items)
+                        .all(|(q, c)| self.unify(q, c))
+            }
+            (
+                Type::Constructor {
+                    name: query_name,
+                    args: query_args,
+                },
+                Type::Constructor {
+                    name: candidate_name,
+                    args: candidate_args,
+                },
+            ) => {
+                same_constructor(query_name, candidate_name)
+                    && query_args.len() == candidate_args.len()
+                    && query_args
+                        .iter()
+                        .zip(candidate_args)
+                        .all(|(q, c)| self.unify(q, c))
+            }
+            (
+                Type::Record {
+                    fields: query_fields,
+                    ..
+                },
+                Type::Record {
+                    fields: candidate_fields,
+                    extends: candidate_extends,
+                },
+            ) => {
+                if has_duplicate_fields(query_fields) || has_duplicate_fields(candidate_fields) {
+                    return false;
+                }
+                // An extensible candidate like `{ r | x : Int }` accepts any
+                // record that has all of its fields, so extra query fields
+                // are allowed there
+                let mut matched = 0;
+                let mut absorbed = 0;
+                for (field_name, query_field) in query_fields {
+                    match candidate_fields.iter().find(|(name, _)| name == field_name) {
+                        Some((_, candidate_field)) => {
+                            if !self.unify(query_field, candidate_field) {
+                                return false;
+                            }
+                            matched += 1;
+                        }
+                        None if candidate_extends.is_some() => absorbed += 1,
+                        None => return false,
+                    }
+                }
+                if candidate_extends.is_some() && matched < candidate_fields.len() {
+                    return false;
+                }
+                self.penalty += (candidate_fields.len().saturating_sub(matched) + absorbed) as u32;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Structural equality that ignores module qualification
+fn equivalent(a: &Type, b: &Type) -> bool {
+    match (a, b) {
+        (Type::Var { name: a }, Type::Var { name: b }) => a == b,
+        (
+            Type::Lambda {
+                arg: a_arg,
+                result: a_result,
+            },
+            Type::Lambda {
+                arg: b_arg,
+                result: b_result,
+            },
+        ) => equivalent(a_arg, b_arg) && equivalent(a_result, b_result),
+        (Type::Tuple { items: a }, Type::Tuple { items: b }) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equivalent(a, b))
+        }
+        (
+            Type::Constructor {
+                name: a_name,
+                args: a_args,
+            },
+            Type::Constructor {
+                name: b_name,
+                args: b_args,
+            },
+        ) => {
+            same_constructor(a_name, b_name)
+                && a_args.len() == b_args.len()
+                && a_args.iter().zip(b_args).all(|(a, b)| equivalent(a, b))
+        }
+        (Type::Record { fields: a, .. }, Type::Record { fields: b, .. }) => {
+            a.len() == b.len()
+                && !has_duplicate_fields(a)
+                && !has_duplicate_fields(b)
+                && a.iter().all(|(name, a_field)| {
+                    b.iter()
+                        .any(|(other, b_field)| name == other && equivalent(a_field, b_field))
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Whether a record names a field twice, which Elm rejects; such a record
+/// matches nothing
+fn has_duplicate_fields(fields: &[(String, Type)]) -> bool {
+    fields
+        .iter()
+        .enumerate()
+        .any(|(i, (name, _))| fields[..i].iter().any(|(other, _)| other == name))
+}
+
+fn satisfies_constraint(var: &str, typ: &Type) -> bool {
+    let Some(kind) = constraint(var) else {
+        return true;
+    };
+    match typ {
+        Type::Var { name } => constraint(name) == Some(kind),
+        Type::Constructor { name, args } => match (kind, unqualified(name)) {
+            ("number", "Int" | "Float") => true,
+            ("comparable", "Int" | "Float" | "Char" | "String") => true,
+            ("comparable" | "compappend", "List") => args
+                .iter()
+                .all(|arg| satisfies_constraint("comparable", arg)),
+            ("appendable", "List") => true,
+            ("appendable" | "compappend", "String") => true,
+            _ => false,
+        },
+        Type::Tuple { items } => {
+            kind == "comparable"
+                && items.len() <= 3
+                && items
+                    .iter()
+                    .all(|item| satisfies_constraint("comparable", item))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::annotation;
+
+    fn dist(query: &str, candidate: &str) -> Option<u32> {
+        distance(
+            &annotation::parse(query).unwrap(),
+            &annotation::parse(candidate).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_exact_match_with_renaming() {
+        assert_eq!(
+            dist(
+                "(x -> y) -> List x -> List y",
+                "(a -> b) -> List.List a -> List.List b"
+            ),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_renaming_fails() {
+        assert_eq!(
+            dist(
+                "(a -> a) -> List a -> List a",
+                "(a -> b) -> List a -> List b"
+            ),
+            None
+        );
+        assert_eq!(dist("a -> b -> a", "a -> a -> a"), None);
+    }
+
+    #[test]
+    fn test_argument_reordering() {
+        let in_order = dist(
+            "(a -> b) -> List a -> List b",
+            "(a -> b) -> List a -> List b",
+        );
+        let swapped = dist(
+            "List a -> (a -> b) -> List b",
+            "(a -> b) -> List a -> List b",
+        );
+        assert_eq!(in_order, Some(0));
+        assert!(swapped.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_instantiation_and_constraints() {
+        assert_eq!(dist("List Int -> Int", "List number -> number"), Some(1));
+        assert_eq!(dist("List String -> String", "List number -> number"), None);
+        assert_eq!(dist("Maybe Int -> Int", "Maybe a -> a"), Some(1));
+        assert_eq!(
+            dist("comparable -> comparable", "comparable -> comparable"),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_records() {
+        assert_eq!(dist("{ x : Int } -> Int", "{ x : Int } -> Int"), Some(0));
+        assert_eq!(
+            dist("{ x : Int } -> Int", "{ x : Int, y : Int } -> Int"),
+            Some(1)
+        );
+        assert_eq!(dist("{ z : Int } -> Int", "{ x : Int } -> Int"), None);
+    }
+
+    #[test]
+    fn test_records_with_duplicate_fields() {
+        assert!(annotation::parse("{ x : Int, x : Int } -> Int").is_err());
+
+        // Built directly, as docs from elsewhere could still hold one
+        let int = annotation::parse("Int").unwrap();
+        let duplicated = Type::Record {
+            fields: vec![("x".to_string(), int.clone()), ("x".to_string(), int)],
+            extends: None,
+        };
+        let candidate = annotation::parse("{ x : Int }").unwrap();
+        assert_eq!(distance(&duplicated, &candidate), None);
+        assert_eq!(distance(&candidate, &duplicated), None);
+
+        let other = annotation::parse("{ x : Int, y : Int }").unwrap();
+        assert!(!equivalent(&duplicated, &other));
+    }
+
+    #[test]
+    fn test_extensible_records() {
+        assert_eq!(
+            dist("{ x : Int, y : Int } -> Int", "{ r | x : Int } -> Int"),
+            Some(1)
+        );
+        assert_eq!(
+            dist("{ x : Int } -> Int", "{ r | x : Int } -> Int"),
+            Some(0)
+        );
+        assert_eq!(dist("{ y : Int } -> Int", "{ r | x : Int } -> Int"), None);
+    }
+
+    #[test]
+    fn test_closer_matches_rank_first() {
+        let query = "(a -> b) -> List a -> List b";
+        let exact = dist(query, "(a -> b) -> List a -> List b").unwrap();
+        let specific = dist(query, "(a -> Int) -> List a -> List Int").unwrap();
+        assert!(exact < specific);
+        assert_eq!(dist(query, "(a -> b) -> Array a -> Array b"), None);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::elm::reader::{self, Section};
+use crate::elm::source::{self, SourceFile};
+use crate::elm::{fetcher, PackageInfo};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Every project needs elm/core, and its `Basics`, `List`, `Maybe` and other
+/// default imports are used without an `import` line
+const CORE: &str = "elm/core";
+
+/// A direct dependency none of whose modules are imported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnusedDependency {
+    pub package: String,
+    pub version: String,
+}
+
+/// An imported module that is only available through a package that isn't a
+/// direct dependency. The compiler rejects these imports; the package must
+/// be moved to direct dependencies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MissingDependency {
+    pub module: String,
+    pub package: String,
+    pub version: String,
+    /// Where elm.json lists the package now
+    #[serde(rename = "type")]
+    pub section: Section,
+    pub files: Vec<String>,
+}
+
+/// An imported module that neither the project nor any dependency provides
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnknownImport {
+    pub module: String,
+    pub files: Vec<String>,
+}
+
+/// A dependency whose elm.json couldn't be read, so its modules are unknown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnreadablePackage {
+    pub package: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyUsage {
+    pub files_scanned: usize,
+    pub unused: Vec<UnusedDependency>,
+    pub missing: Vec<MissingDependency>,
+    pub unknown_imports: Vec<UnknownImport>,
+    pub unreadable: Vec<UnreadablePackage>,
+}
+
+/// Compare the imports in `sources` with the packages in `elm_json`. `load`
+/// reads a dependency's own elm.json to learn which modules it exposes.
+pub fn analyze(
+    elm_json: &Value,
+    sources: &[SourceFile],
+    load: impl Fn(&PackageInfo) -> Result<Value, String>,
+) -> DependencyUsage {
+    let elm_version = reader::get_elm_version(elm_json);
+
+    // Which packages expose each module, in elm.json order: direct first
+    let mut providers: HashMap<String, Vec<(String, String, Section)>> = HashMap::new();
+    let mut direct: Vec<(String, String)> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut unreadable = Vec::new();
+    for section in Section::ALL {
+        for dependency in reader::get_dependencies(elm_json, section) {
+            let package = dependency.resolve(elm_version.as_deref());
+            let name = format!("{}/{}", package.author, package.name);
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let package_json = match load(&package) {
+                Ok(package_json) => package_json,
+                Err(error) => {
+                    unreadable.push(UnreadablePackage {
+                        package: name,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            for module in reader::get_exposed_modules(&package_json) {
+                providers.entry(module).or_default().push((
+                    name.clone(),
+                    package.version.clone(),
+                    section,
+                ));
+            }
+            if section == Section::Direct {
+                direct.push((name, package.version));
+            }
+        }
+    }
+
+    let local: HashSet<&str> = sources.iter().map(|s| s.module.as_str()).collect();
+    let mut imports: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for source in sources {
+        for module in &source.imports {
+            // Kernel modules are only importable by elm/* packages themselves
+            if local.contains(module.as_str()) || module.starts_with("Elm.Kernel.") {
+                continue;
+            }
+            let files = imports.entry(module.as_str()).or_default();
+            if !files.contains(&source.path) {
+                files.push(source.path.clone());
+            }
+        }
+    }
+
+    let mut used: HashSet<&str> = HashSet::from([CORE]);
+    let mut missing = Vec::new();
+    let mut unknown_imports = Vec::new();
+    for (module, files) in imports {
+        let Some(candidates) = providers.get(module) else {
+            unknown_imports.push(UnknownImport {
+                module: module.to_string(),
+                files,
+            });
+            continue;
+        };
+        match candidates.iter().find(|(_, _, s)| *s == Section::Direct) {
+            Some((package, _, _)) => {
+                used.insert(package);
+            }
+            None => {
+                let (package, version, section) = &candidates[0];
+                missing.push(MissingDependency {
+                    module: module.to_string(),
+                    package: package.clone(),
+                    version: version.clone(),
+                    section: *section,
+                    files,
+                });
+            }
+        }
+    }
+
+    let unused = direct
+        .iter()
+        .filter(|(package, _)| !used.contains(package.as_str()))
+        .map(|(package, version)| UnusedDependency {
+            package: package.clone(),
+            version: version.clone(),
+        })
+        .collect();
+
+    DependencyUsage {
+        files_scanned: sources.len(),
+        unused,
+        missing,
+        unknown_imports,
+        unreadable,
+    }
+}
+
+/// Scan the project in `project_dir` and analyze it, reading each package's
+/// elm.json from the local Elm cache or the registry
+pub fn for_project(elm_json: &Value, project_dir: &Path) -> Result<DependencyUsage, String> {
+    let sources = source::scan_project(elm_json, project_dir)?;
+    let elm_version = reader::get_elm_version(elm_json);
+    Ok(analyze(elm_json, &sources, |package| {
+        fetcher::fetch_elm_json(package, elm_version.as_deref())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(path: &str, module: &str, imports: &[&str]) -> SourceFile {
+        SourceFile {
+            path: path.to_string(),
+            module: module.to_string(),
+            imports: imports.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    fn load(package: &PackageInfo) -> Result<Value, String> {
+        let modules: &[&str] = match package.name.as_str() {
+            "core" => &["Basics", "List", "Task"],
+            "html" => &["Html", "Html.Attributes"],
+            "json" => &["Json.Decode", "Json.Encode"],
+            "url" => &["Url", "Url.Parser"],
+            "test" => &["Test", "Expect"],
+            _ => return Err(format!("no elm.json for {}", package.name)),
+        };
+        Ok(json!({"exposed-modules": modules}))
+    }
+
+    #[test]
+    fn test_analyze() {
+        let elm_json = json!({
+            "type": "application",
+            "source-directories": ["src"],
+            "elm-version": "0.19.1",
+            "dependencies": {
+                "direct": {"elm/core": "1.0.5", "elm/html": "1.0.0", "elm/url": "1.0.0"},
+                "indirect": {"elm/json": "1.1.3", "elm/mystery": "1.0.0"}
+            },
+            "test-dependencies": {
+                "direct": {"elm-explorations/test": "2.1.1"},
+                "indirect": {}
+            }
+        });
+        let sources = vec![
+            source("src/Main.elm", "Main", &["Html", "Json.Decode", "Page"]),
+            source(
+                "src/Page.elm",
+                "Page",
+                &["Html.Attributes", "Json.Decode", "Expect", "Nowhere"],
+            ),
+        ];
+
+        let usage = analyze(&elm_json, &sources, load);
+        assert_eq!(usage.files_scanned, 2);
+        assert_eq!(
+            usage.unused,
+            vec![UnusedDependency {
+                package: "elm/url".to_string(),
+                version: "1.0.0".to_string()
+            }]
+        );
+
+        let missing: Vec<(&str, &str, Section, usize)> = usage
+            .missing
+            .iter()
+            .map(|m| {
+                (
+                    m.module.as_str(),
+                    m.package.as_str(),
+                    m.section,
+                    m.files.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                ("Expect", "elm-explorations/test", Section::TestDirect, 1),
+                ("Json.Decode", "elm/json", Section::Indirect, 2),
+            ]
+        );
+
+        assert_eq!(usage.unknown_imports.len(), 1);
+        assert_eq!(usage.unknown_imports[0].module, "Nowhere");
+        assert_eq!(usage.unreadable.len(), 1);
+        assert_eq!(usage.unreadable[0].package, "elm/mystery");
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A package or compiler version, e.g. `1.0.5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub fn parse(input: &str) -> Result<Version, String> {
+        let parts: Vec<&str> = input.trim().split('.').collect();
+        let numbers: Vec<u32> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+        match numbers[..] {
+            [major, minor, patch] if parts.len() == 3 => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(format!("Invalid version '{}'", input.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A version requirement from elm.json. Applications pin exact versions
+/// (`"1.0.5"`); packages use ranges (`"1.0.0 <= v < 2.0.0"`). An exact
+/// version is represented as the range `v <= x <= v`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub lower: Version,
+    pub lower_inclusive: bool,
+    pub upper: Version,
+    pub upper_inclusive: bool,
+}
+
+impl Constraint {
+    pub fn exact(version: Version) -> Constraint {
+        Constraint {
+            lower: version,
+            lower_inclusive: true,
+            upper: version,
+            upper_inclusive: true,
+        }
+    }
+
+    /// Any version at all
+    pub fn anything() -> Constraint {
+        Constraint {
+            lower: Version {
+                major: 0,
+                minor: 0,
+                patch: 0,
+            },
+            lower_inclusive: true,
+            upper: Version {
+                major: u32::MAX,
+                minor: 0,
+                patch: 0,
+            },
+            upper_inclusive: false,
+        }
+    }
+
+    /// `version <= v < next minor`, allowing patch releases
+    pub fn until_next_minor(version: Version) -> Constraint {
+        Constraint {
+            lower: version,
+            lower_inclusive: true,
+            upper: Version {
+                major: version.major,
+                minor: version.minor + 1,
+                patch: 0,
+            },
+            upper_inclusive: false,
+        }
+    }
+
+    /// `version <= v < next major`, the range `elm install` writes into a
+    /// package's elm.json
+    pub fn until_next_major(version: Version) -> Constraint {
+        Constraint {
+            lower: version,
+            lower_inclusive: true,
+            upper: Version {
+                major: version.major + 1,
+                minor: 0,
+                patch: 0,
+            },
+            upper_inclusive: false,
+        }
+    }
+
+    /// Parse either an exact version or a range like `1.0.0 <= v < 2.0.0`
+    pub fn parse(input: &str) -> Result<Constraint, String> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        match parts[..] {
+            [version] => Ok(Constraint::exact(Version::parse(version)?)),
+            [lower, lower_op, "v", upper_op, upper] => {
+                let inclusive = |op: &str| match op {
+                    "<=" => Ok(true),
+                    "<" => Ok(false),
+                    _ => Err(format!("Invalid operator '{op}' in constraint '{input}'")),
+                };
+                Ok(Constraint {
+                    lower: Version::parse(lower)?,
+                    lower_inclusive: inclusive(lower_op)?,
+                    upper: Version::parse(upper)?,
+                    upper_inclusive: inclusive(upper_op)?,
+                })
+            }
+            _ => Err(format!("Invalid version constraint '{}'", input.trim())),
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.lower == self.upper && self.lower_inclusive && self.upper_inclusive
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        let above = match version.cmp(&self.lower) {
+            Ordering::Greater => true,
+            Ordering::Equal => self.lower_inclusive,
+            Ordering::Less => false,
+        };
+        let below = match version.cmp(&self.upper) {
+            Ordering::Less => true,
+            Ordering::Equal => self.upper_inclusive,
+            Ordering::Greater => false,
+        };
+        above && below
+    }
+
+    /// The highest of `versions` that satisfies the constraint
+    pub fn best<'a>(&self, versions: impl IntoIterator<Item = &'a Version>) -> Option<Version> {
+        versions
+            .into_iter()
+            .filter(|v| self.contains(v))
+            .max()
+            .copied()
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_exact() {
+            return write!(f, "{}", self.lower);
+        }
+        let op = |inclusive| if inclusive { "<=" } else { "<" };
+        write!(
+            f,
+            "{} {} v {} {}",
+            self.lower,
+            op(self.lower_inclusive),
+            op(self.upper_inclusive),
+            self.upper
+        )
+    }
+}
+
+impl Serialize for Constraint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(input: &str) -> Version {
+        Version::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            v("1.2.3"),
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3
+            }
+        );
+        assert!(v("1.10.0") > v("1.9.9"));
+        assert!(Version::parse("1.2").is_err());
+        assert!(Version::parse("1.2.x").is_err());
+    }
+
+    #[test]
+    fn test_parse_constraint() {
+        let range = Constraint::parse("1.0.0 <= v < 2.0.0").unwrap();
+        assert!(!range.is_exact());
+        assert!(range.contains(&v("1.0.0")));
+        assert!(range.contains(&v("1.9.9")));
+        assert!(!range.contains(&v("2.0.0")));
+        assert!(!range.contains(&v("0.9.0")));
+        assert_eq!(range.to_string(), "1.0.0 <= v < 2.0.0");
+
+        let exact = Constraint::parse("1.0.5").unwrap();
+        assert!(exact.is_exact());
+        assert!(exact.contains(&v("1.0.5")));
+        assert!(!exact.contains(&v("1.0.6")));
+        assert_eq!(exact.to_string(), "1.0.5");
+
+        assert!(Constraint::parse("1.0.0 => v < 2.0.0").is_err());
+        assert!(Constraint::parse("1.0.0 <= x").is_err());
+    }
+
+    #[test]
+    fn test_constructed_constraints() {
+        assert_eq!(
+            Constraint::until_next_major(v("1.2.3")).to_string(),
+            "1.2.3 <= v < 2.0.0"
+        );
+        assert_eq!(
+            Constraint::until_next_minor(v("1.2.3")).to_string(),
+            "1.2.3 <= v < 1.3.0"
+        );
+        assert!(Constraint::anything().contains(&v("0.0.1")));
+        assert!(Constraint::anything().contains(&v("99.0.0")));
+    }
+
+    #[test]
+    fn test_best_version() {
+        let range = Constraint::parse("1.0.0 <= v < 2.0.0").unwrap();
+        let installed = [v("0.9.0"), v("1.0.2"), v("1.1.0"), v("2.0.0")];
+        assert_eq!(range.best(&installed), Some(v("1.1.0")));
+        assert_eq!(range.best(&[v("2.0.0")]), None);
+    }
+}
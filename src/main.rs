@@ -1,6 +1,7 @@
 mod elm;
 mod mcp;
 
+use crate::mcp::project;
 use crate::mcp::prompts::{prompts_get, prompts_list};
 use crate::mcp::resources::{resource_read, resources_list};
 use crate::mcp::tools::register_tools;
@@ -41,6 +42,9 @@ async fn main() {
         display_info(&args);
         return;
     }
+    if let Some(project) = args.project.clone() {
+        project::set_project_override(project);
+    }
     // signal handling to exit cli
     let mut signals = Signals::new([SIGTERM, SIGINT]).unwrap();
     thread::spawn(move || {
@@ -63,16 +67,32 @@ async fn main() {
         writeln!(logging_file, "{line}").unwrap();
         if !line.is_empty() {
             if let Ok(json_value) = serde_json::from_str::<Value>(&line) {
+                // responses from the client to our own requests
+                if json_value.is_object() && json_value.get("method").is_none() {
+                    project::handle_response(&json_value);
+                }
                 // notifications, no response required
-                if json_value.is_object() && json_value.get("id").is_none() {
-                    if let Some(method) = json_value.get("method") {
-                        if method == "notifications/initialized" {
-                            notifications_initialized();
-                        } else if method == "notifications/cancelled" {
-                            let params_value = json_value.get("params").unwrap();
-                            let cancel_params: CancelledNotification =
-                                serde_json::from_value(params_value.clone()).unwrap();
-                            notifications_cancelled(cancel_params);
+                else if json_value.is_object() && json_value.get("id").is_none() {
+                    if let Some(method) = json_value.get("method").and_then(|m| m.as_str()) {
+                        // some notifications lead us to send the client a request
+                        let request = match method {
+                            "notifications/initialized" => notifications_initialized(),
+                            "notifications/roots/list_changed" => {
+                                notifications_roots_list_changed()
+                            }
+                            "notifications/cancelled" => {
+                                let params_value = json_value.get("params").unwrap();
+                                let cancel_params: CancelledNotification =
+                                    serde_json::from_value(params_value.clone()).unwrap();
+                                notifications_cancelled(cancel_params);
+                                None
+                            }
+                            _ => None,
+                        };
+                        if let Some(request) = request {
+                            let request_json = serde_json::to_string(&request).unwrap();
+                            writeln!(logging_file, "{request_json}\n").unwrap();
+                            println!("{request_json}");
                         }
                     }
                 } else if let Ok(mut rpc_request) = Request::from_value(json_value) {
@@ -1,3 +1,4 @@
+pub mod project;
 pub mod prompts;
 pub mod resources;
 pub mod tools;
@@ -0,0 +1,388 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use url::Url;
+
+/// Prefix of the ids of `roots/list` requests we send to the client, so their
+/// responses can be told apart from anything else
+const ROOTS_REQUEST_ID_PREFIX: &str = "elm-package-mcp-server/roots-list/";
+
+/// The `--project` command line flag
+static PROJECT_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+/// Whether the client said it supports `roots/list` when initializing
+static CLIENT_SUPPORTS_ROOTS: AtomicBool = AtomicBool::new(false);
+/// The client's workspace roots, from its last `roots/list` response
+static ROOTS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How deep below a workspace directory to look for more elm.json files
+const MAX_DISCOVERY_DEPTH: usize = 5;
+
+pub fn set_project_override(path: PathBuf) {
+    let _ = PROJECT_OVERRIDE.set(path);
+}
+
+pub fn project_override() -> Option<&'static Path> {
+    PROJECT_OVERRIDE.get().map(|p| p.as_path())
+}
+
+pub fn set_client_supports_roots(supported: bool) {
+    CLIENT_SUPPORTS_ROOTS.store(supported, Ordering::Relaxed);
+}
+
+/// The workspace roots last reported by the client
+pub fn roots() -> Vec<PathBuf> {
+    ROOTS.read().map(|roots| roots.clone()).unwrap_or_default()
+}
+
+/// A `roots/list` request to send to the client, if it supports roots
+pub fn roots_list_request() -> Option<Value> {
+    if !CLIENT_SUPPORTS_ROOTS.load(Ordering::Relaxed) {
+        return None;
+    }
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    Some(json!({
+        "jsonrpc": "2.0",
+        "id": format!("{ROOTS_REQUEST_ID_PREFIX}{id}"),
+        "method": "roots/list"
+    }))
+}
+
+/// Handle a response from the client to one of our requests. Returns false if
+/// it wasn't a response to a `roots/list` request.
+pub fn handle_response(response: &Value) -> bool {
+    let is_roots_response = response
+        .get("id")
+        .and_then(|id| id.as_str())
+        .is_some_and(|id| id.starts_with(ROOTS_REQUEST_ID_PREFIX));
+    if !is_roots_response {
+        return false;
+    }
+    // An error response leaves the previous roots in place
+    if let Some(result) = response.get("result") {
+        if let Ok(mut roots) = ROOTS.write() {
+            *roots = parse_roots(result);
+        }
+    }
+    true
+}
+
+/// The local directories in a `roots/list` result. Roots are `file://` URIs;
+/// anything else is ignored.
+fn parse_roots(result: &Value) -> Vec<PathBuf> {
+    result
+        .get("roots")
+        .and_then(|roots| roots.as_array())
+        .map(|roots| {
+            roots
+                .iter()
+                .filter_map(|root| root.get("uri")?.as_str())
+                .filter_map(|uri| Url::parse(uri).ok()?.to_file_path().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The nearest elm.json in `dir` or one of its parents
+fn find_upward(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join("elm.json"))
+        .find(|path| path.is_file())
+}
+
+/// Locate elm.json: the `project_override` (a directory or an elm.json file)
+/// if given, else the nearest one at or above each client root, else the
+/// nearest one at or above `cwd`
+fn resolve_elm_json(
+    project_override: Option<&Path>,
+    roots: &[PathBuf],
+    cwd: Option<&Path>,
+) -> Result<PathBuf, String> {
+    if let Some(project) = project_override {
+        let path = if project.is_dir() {
+            project.join("elm.json")
+        } else {
+            project.to_path_buf()
+        };
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!(
+                "elm.json not found at {} (set with --project)",
+                path.display()
+            ))
+        };
+    }
+
+    if let Some(found) = roots.iter().find_map(|root| find_upward(root)) {
+        return Ok(found);
+    }
+    if let Some(found) = cwd.and_then(find_upward) {
+        return Ok(found);
+    }
+
+    let mut searched: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+    if let Some(cwd) = cwd {
+        searched.push(format!("{} (working directory)", cwd.display()));
+    }
+    Err(format!(
+        "elm.json not found in any of these directories or their parents: {}. Run the server with --project, or from an Elm project directory.",
+        searched.join(", ")
+    ))
+}
+
+/// The path of the project's elm.json, resolved from `--project`, the
+/// client's roots, or the working directory
+pub fn find_elm_json() -> Result<String, String> {
+    let cwd = std::env::current_dir().ok();
+    resolve_elm_json(project_override(), &roots(), cwd.as_deref())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+/// An Elm project in the workspace
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Project {
+    /// The project's directory relative to the workspace directory it was
+    /// found in, or that directory's own name for a project at its top
+    pub name: String,
+    pub elm_json: PathBuf,
+}
+
+/// Every elm.json below `dir`, skipping hidden directories, `elm-stuff` and
+/// `node_modules`
+fn elm_json_files(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    let elm_json = dir.join("elm.json");
+    if elm_json.is_file() {
+        found.push(elm_json);
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            !name.starts_with('.') && name != "elm-stuff" && name != "node_modules"
+        })
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        elm_json_files(&dir, depth - 1, found);
+    }
+}
+
+/// The name of the project whose elm.json is `elm_json`, found below `base`
+fn project_name(base: &Path, elm_json: &Path) -> String {
+    let dir = elm_json.parent().unwrap_or(elm_json);
+    let relative = dir.strip_prefix(base).unwrap_or(dir);
+    if relative.as_os_str().is_empty() {
+        dir.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".".to_string())
+    } else {
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        parts.join("/")
+    }
+}
+
+/// The projects in the workspace directories `bases`, with the `default`
+/// project first. Names are unique; a project whose name is taken is named by
+/// its full path instead.
+fn discover_projects(bases: &[PathBuf], default: Option<&Path>) -> Vec<Project> {
+    let mut found: Vec<(PathBuf, String)> = Vec::new();
+    if let Some(default) = default {
+        let base = bases
+            .iter()
+            .find(|base| default.starts_with(base))
+            .map(|base| base.as_path())
+            .unwrap_or_else(|| default.parent().unwrap_or(default));
+        found.push((default.to_path_buf(), project_name(base, default)));
+    }
+    for base in bases {
+        let mut files = Vec::new();
+        elm_json_files(base, MAX_DISCOVERY_DEPTH, &mut files);
+        for elm_json in files {
+            if !found.iter().any(|(path, _)| *path == elm_json) {
+                let name = project_name(base, &elm_json);
+                found.push((elm_json, name));
+            }
+        }
+    }
+
+    let mut projects: Vec<Project> = Vec::new();
+    for (elm_json, name) in found {
+        let name = if projects.iter().any(|p| p.name == name) {
+            elm_json.parent().unwrap_or(&elm_json).display().to_string()
+        } else {
+            name
+        };
+        projects.push(Project { name, elm_json });
+    }
+    projects
+}
+
+/// Every project in the workspace: below the `--project` directory if set,
+/// else below the client's roots, else below the project found from the
+/// working directory. The project `find_elm_json` picks comes first.
+pub fn projects() -> Vec<Project> {
+    let cwd = std::env::current_dir().ok();
+    let default = resolve_elm_json(project_override(), &roots(), cwd.as_deref()).ok();
+    let bases: Vec<PathBuf> = match project_override() {
+        Some(project) if project.is_dir() => vec![project.to_path_buf()],
+        Some(project) => project
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect(),
+        None if !roots().is_empty() => roots(),
+        None => default
+            .as_deref()
+            .and_then(Path::parent)
+            .or(cwd.as_deref())
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect(),
+    };
+    discover_projects(&bases, default.as_deref())
+}
+
+/// Pick a project by name, or by the path of its directory or elm.json
+fn select_project<'a>(projects: &'a [Project], selector: &str) -> Result<&'a Project, String> {
+    let path = Path::new(selector);
+    projects
+        .iter()
+        .find(|p| p.name == selector)
+        .or_else(|| {
+            projects
+                .iter()
+                .find(|p| p.elm_json == path || p.elm_json.parent() == Some(path))
+        })
+        .ok_or_else(|| {
+            let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+            format!(
+                "Unknown project '{}'. Known projects: {}",
+                selector,
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+}
+
+/// The path of the elm.json of the named `project`, or the default project's
+/// as found by `find_elm_json` if none is named
+pub fn elm_json_for(project: Option<&str>) -> Result<String, String> {
+    match project {
+        None => find_elm_json(),
+        Some(selector) => {
+            select_project(&projects(), selector).map(|p| p.elm_json.to_string_lossy().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elm::test_dir::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_parse_roots() {
+        let result = json!({"roots": [
+            {"uri": "file:///home/me/app", "name": "app"},
+            {"uri": "https://example.com/repo"},
+            {"name": "no uri"}
+        ]});
+        assert_eq!(parse_roots(&result), vec![PathBuf::from("/home/me/app")]);
+        assert!(parse_roots(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_elm_json() {
+        let base = TestDir::new("project");
+        let (app, other) = (base.join("app"), base.join("other"));
+        fs::create_dir_all(app.join("src/Page")).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        fs::write(app.join("elm.json"), "{}").unwrap();
+        let app_json = app.join("elm.json");
+
+        // Roots win over the working directory, and are searched upward
+        let roots = vec![other.clone(), app.join("src/Page")];
+        assert_eq!(
+            resolve_elm_json(None, &roots, Some(&other)).unwrap(),
+            app_json
+        );
+        assert_eq!(resolve_elm_json(None, &[], Some(&app)).unwrap(), app_json);
+
+        // --project accepts a directory or the file itself
+        assert_eq!(
+            resolve_elm_json(Some(&app), &[], Some(&other)).unwrap(),
+            app_json
+        );
+        assert_eq!(
+            resolve_elm_json(Some(&app_json), &[], None).unwrap(),
+            app_json
+        );
+        assert!(resolve_elm_json(Some(&other), &roots, Some(&app)).is_err());
+
+        let error = resolve_elm_json(None, std::slice::from_ref(&other), Some(&other)).unwrap_err();
+        assert!(error.contains("working directory"), "{error}");
+    }
+
+    #[test]
+    fn test_handle_response() {
+        assert!(!handle_response(&json!({"id": 1, "result": {}})));
+        assert!(handle_response(&json!({
+            "id": format!("{ROOTS_REQUEST_ID_PREFIX}99"),
+            "error": {"code": -32601, "message": "Method not found"}
+        })));
+    }
+
+    #[test]
+    fn test_discover_projects() {
+        let base = TestDir::new("workspace");
+        for dir in [
+            "repo",
+            "repo/review",
+            "repo/packages/shared",
+            "repo/elm-stuff/0.19.1",
+            "repo/node_modules/pkg",
+        ] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+            fs::write(base.join(dir).join("elm.json"), "{}").unwrap();
+        }
+        let repo = base.join("repo");
+        let review = repo.join("review/elm.json");
+
+        // The default project comes first, named relative to the workspace
+        let projects = discover_projects(std::slice::from_ref(&repo), Some(&review));
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "repo", "packages/shared"]);
+
+        assert_eq!(
+            select_project(&projects, "review").unwrap().elm_json,
+            review
+        );
+        assert_eq!(
+            select_project(&projects, &repo.join("packages/shared").to_string_lossy())
+                .unwrap()
+                .name,
+            "packages/shared"
+        );
+        let error = select_project(&projects, "tests").unwrap_err();
+        assert!(error.contains("repo, packages/shared"), "{error}");
+    }
+}
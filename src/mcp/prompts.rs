@@ -151,7 +151,7 @@ pub async fn prompts_get(request: GetPromptRequest) -> HandlerResult<PromptResul
                         content: PromptMessageContent {
                             type_name: "text".to_string(),
                             text: format!(
-                                "I need to '{}' in Elm. First, search the package registry using search_packages to find relevant packages. Then check which are already in my project with list_installed_packages. For promising packages, explore them with get_elm_package_exports to find specific functions. Provide function names, type signatures, and usage examples. If no existing packages help, suggest searching for alternatives.",
+                                "I need to '{}' in Elm. First, use search_docs to search the documentation of every package already in my project; if you can describe the function as a type, also try search_by_type. Use get_elm_package_export_docs on the best matches for details. If nothing in my dependencies helps, search the package registry using search_packages and explore promising packages with get_elm_package_exports. Provide function names, type signatures, and usage examples.",
                                 capability
                             ),
                         },
@@ -178,7 +178,7 @@ pub async fn prompts_get(request: GetPromptRequest) -> HandlerResult<PromptResul
                         content: PromptMessageContent {
                             type_name: "text".to_string(),
                             text: format!(
-                                "I'm trying to use the '{}' module in Elm. Please help me understand what's available. First, determine which package provides this module by checking my dependencies. Then fetch the exports for this specific module and explain: 1) All available functions with their type signatures, 2) Common usage patterns, 3) What I can import from this module.",
+                                "I'm trying to use the '{}' module in Elm. Please help me understand what's available. First, use find_module to find which package provides this module and whether it's a direct dependency in my elm.json; if it isn't, say which package to install (use plan_install to check it's compatible). Then fetch the exports for this specific module and explain: 1) All available functions with their type signatures, 2) Common usage patterns, 3) What I can import from this module.",
                                 module_path
                             ),
                         },
@@ -1,3 +1,4 @@
+use crate::mcp::project::find_elm_json;
 use crate::mcp::types::*;
 use rpc_router::{HandlerResult, IntoHandlerError};
 use serde_json::json;
@@ -46,26 +47,3 @@ pub async fn resource_read(request: ReadResourceRequest) -> HandlerResult<ReadRe
         )
     }
 }
-
-fn find_elm_json() -> Result<String, String> {
-    let current_dir =
-        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {e}"))?;
-
-    let mut dir = current_dir.as_path();
-
-    loop {
-        let elm_json_path = dir.join("elm.json");
-        if elm_json_path.exists() {
-            return Ok(elm_json_path.to_string_lossy().to_string());
-        }
-
-        match dir.parent() {
-            Some(parent) => dir = parent,
-            None => {
-                return Err(
-                    "elm.json not found in current directory or any parent directory".to_string(),
-                )
-            }
-        }
-    }
-}
@@ -1,11 +1,15 @@
-use crate::elm::{fetcher, reader, search, PackageInfo};
+use crate::elm::{
+    annotation, api_diff, cache, doc_search, docs_cache, docs_mirror, fetcher, graph, home,
+    license, module_docs, module_index, project_docs, ranking, reader, registry, releases, search,
+    snapshot, solver, type_search, usage, version, PackageInfo,
+};
+use crate::mcp::project::{self, find_elm_json};
 use crate::mcp::types::*;
 use maplit::hashmap;
 use rpc_router::{Handler, HandlerResult, IntoHandlerError, RouterBuilder, RpcParams};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashSet;
-use std::env;
 
 const DEPRECATION_WARNING: &str = "⚠️ DEPRECATED: This MCP server is deprecated. Use the `migrate-to-skills` prompt for migration instructions, or install the new plugin: /plugin marketplace add caseyWebb/elm-claude-plugin\n\n";
 
@@ -18,6 +22,26 @@ pub fn register_tools(router_builder: RouterBuilder) -> RouterBuilder {
         .append_dyn("get_elm_package_readme", get_readme.into_dyn())
         .append_dyn("get_elm_package_exports", get_exports.into_dyn())
         .append_dyn("get_elm_package_export_docs", get_export_docs.into_dyn())
+        .append_dyn("search_by_type", search_by_type.into_dyn())
+        .append_dyn("get_server_diagnostics", get_diagnostics.into_dyn())
+        .append_dyn("search_docs", search_docs.into_dyn())
+        .append_dyn("get_elm_module_docs", get_module_docs.into_dyn())
+        .append_dyn("diff_package_api", diff_package_api.into_dyn())
+        .append_dyn("dependency_graph", dependency_graph.into_dyn())
+        .append_dyn("why_dependency", why_dependency.into_dyn())
+        .append_dyn("plan_install", plan_install.into_dyn())
+        .append_dyn("check_dependency_usage", check_dependency_usage.into_dyn())
+        .append_dyn("find_module", find_module.into_dyn())
+        .append_dyn("get_project_exports", get_project_exports.into_dyn())
+        .append_dyn(
+            "get_project_export_docs",
+            get_project_export_docs.into_dyn(),
+        )
+        .append_dyn("list_projects", list_projects.into_dyn())
+        .append_dyn("license_report", license_report.into_dyn())
+        .append_dyn("get_elm_package_info", get_package_info.into_dyn())
+        .append_dyn("get_package_releases", get_package_releases.into_dyn())
+        .append_dyn("search_registry_docs", search_registry_docs.into_dyn())
 }
 
 pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<ListToolsResult> {
@@ -25,7 +49,7 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
         tools: vec![
             Tool {
                 name: "list_installed_packages".to_string(),
-                description: Some("List all Elm packages from elm.json file. Returns direct and indirect dependencies with their versions.\n\n**Use this when:** User asks about dependencies in their current project.\n\n**Next steps:** After listing packages, use get_elm_package_readme for overview or get_elm_package_exports to browse available functions.".to_string()),
+                description: Some("List all Elm packages from elm.json file. Returns direct and indirect dependencies, and optionally test-dependencies, with their versions.\n\n**Use this when:** User asks about dependencies in their current project, or you need the version of a test package (e.g., elm-explorations/test for `Expect` and `Fuzz`) while writing tests.\n\n**Next steps:** After listing packages, use get_elm_package_readme for overview or get_elm_package_exports to browse available functions.".to_string()),
                 input_schema: ToolInputSchema {
                     type_name: "object".to_string(),
                     properties: hashmap! {
@@ -33,7 +57,17 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                             type_name: Some("boolean".to_string()),
                             description: Some("Include indirect dependencies (default: false). Set to true when doing comprehensive dependency analysis.".to_string()),
                             enum_values: None,
-                        }
+                        },
+                        "scope".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Which dependencies to list: 'dependencies' (default), 'test' for test-dependencies only, or 'all' for both".to_string()),
+                            enum_values: Some(vec![
+                                "dependencies".to_string(),
+                                "test".to_string(),
+                                "all".to_string(),
+                            ]),
+                        },
+                        "project".to_string() => project_property()
                     },
                     required: vec![],
                 },
@@ -46,14 +80,25 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                     properties: hashmap! {
                         "query".to_string() => ToolInputSchemaProperty {
                             type_name: Some("string".to_string()),
-                            description: Some("Search query - can be package name, keywords, or description of what you're looking for (e.g., 'json decode', 'http', 'date formatting')".to_string()),
+                            description: Some("Search query - can be package name, keywords, or description of what you're looking for (e.g., 'json decode', 'http', 'date formatting'). Narrow it with filter terms: 'author:elm' (a trailing * matches any suffix, e.g. 'author:elm*'), 'license:MIT', 'name:json' or 'summary:\"date format\"' to match only that field, and a leading '-' to exclude, e.g. '-author:someone'. Several author: or license: terms match any of them. A query of only filters lists every matching package. Results rank packages named after the query first, with a boost for elm, elm-explorations and elm-community packages.".to_string()),
+                            enum_values: None,
+                        },
+                        "limit".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Maximum number of results (default: 20)".to_string()),
+                            enum_values: None,
+                        },
+                        "offset".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Number of results to skip, for paging through more than `limit` (default: 0). The response's `total` is the number of matches.".to_string()),
                             enum_values: None,
                         },
                         "already_included".to_string() => ToolInputSchemaProperty {
                             type_name: Some("boolean".to_string()),
                             description: Some("Include packages already in elm.json (default: true). Set to false to only show packages not yet installed, useful for finding alternatives.".to_string()),
                             enum_values: None,
-                        }
+                        },
+                        "project".to_string() => project_property()
                     },
                     required: vec!["query".to_string()],
                 },
@@ -78,7 +123,8 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                             type_name: Some("string".to_string()),
                             description: Some("Package version (e.g., '1.0.5'). Get this from list_installed_packages output to use the exact version in the project.".to_string()),
                             enum_values: None,
-                        }
+                        },
+                        "project".to_string() => project_property()
                     },
                     required: vec![
                         "author".to_string(),
@@ -113,7 +159,8 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                             type_name: Some("string".to_string()),
                             description: Some("Optional: Filter to specific module (e.g., 'List', 'Maybe', 'Json.Decode'). Use when user asks about a specific module or import.".to_string()),
                             enum_values: None,
-                        }
+                        },
+                        "project".to_string() => project_property()
                     },
                     required: vec![
                         "author".to_string(),
@@ -152,7 +199,8 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                             type_name: Some("string".to_string()),
                             description: Some("Name of the specific export (e.g., 'map', 'Maybe', 'andThen'). Get from get_elm_package_exports output.".to_string()),
                             enum_values: None,
-                        }
+                        },
+                        "project".to_string() => project_property()
                     },
                     required: vec![
                         "author".to_string(),
@@ -163,329 +211,1677 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
                     ],
                 },
             },
-        ],
-        next_cursor: None,
-    };
-    Ok(response)
-}
-
-#[derive(Deserialize, Serialize, RpcParams)]
-pub struct ListInstalledRequest {
-    pub include_indirect: Option<bool>,
-}
-
-pub async fn list_installed(request: ListInstalledRequest) -> HandlerResult<CallToolResult> {
-    let elm_json_path =
-        find_elm_json().map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-    let elm_json = reader::read_elm_json(&elm_json_path)
-        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-
-    let include_indirect = request.include_indirect.unwrap_or(false);
-
-    let mut packages = reader::get_direct_packages(&elm_json);
-    let mut package_list: Vec<Value> = packages
-        .iter()
-        .map(|p| {
-            json!({
-                "author": p.author,
-                "name": p.name,
-                "version": p.version,
-                "type": "direct"
-            })
-        })
-        .collect();
-
-    if include_indirect {
-        let indirect_packages = reader::get_indirect_packages(&elm_json);
-        let indirect_list: Vec<Value> = indirect_packages
-            .iter()
-            .map(|p| {
-                json!({
-                    "author": p.author,
-                    "name": p.name,
-                    "version": p.version,
-                    "type": "indirect"
-                })
-            })
-            .collect();
-        package_list.extend(indirect_list);
-        packages.extend(indirect_packages);
-    }
-
-    let result = json!({
-        "packages": package_list,
-        "total": package_list.len(),
-        "direct_count": reader::get_direct_packages(&elm_json).len(),
-        "indirect_count": reader::get_indirect_packages(&elm_json).len()
-    });
-
-    Ok(CallToolResult {
-        content: vec![CallToolResultContent::Text {
-            text: format!("{}{}", DEPRECATION_WARNING, serde_json::to_string_pretty(&result).unwrap()),
-        }],
-        is_error: false,
-    })
-}
-
-#[derive(Deserialize, Serialize, RpcParams)]
-pub struct GetReadmeRequest {
-    pub author: String,
-    pub name: String,
-    pub version: String,
-}
-
-pub async fn get_readme(request: GetReadmeRequest) -> HandlerResult<CallToolResult> {
-    let package_info = PackageInfo {
-        author: request.author,
-        name: request.name,
-        version: request.version,
-    };
-
-    let readme = fetcher::fetch_readme(&package_info)
-        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-
-    Ok(CallToolResult {
-        content: vec![CallToolResultContent::Text { text: format!("{}{}", DEPRECATION_WARNING, readme) }],
-        is_error: false,
-    })
-}
-
-#[derive(Deserialize, Serialize, RpcParams)]
-pub struct GetExportsRequest {
-    pub author: String,
-    pub name: String,
-    pub version: String,
-    pub module: Option<String>,
-}
-
-pub async fn get_exports(request: GetExportsRequest) -> HandlerResult<CallToolResult> {
-    let package_info = PackageInfo {
-        author: request.author.clone(),
-        name: request.name.clone(),
-        version: request.version.clone(),
-    };
-
-    let modules = fetcher::fetch_docs(&package_info)
-        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-
-    // Filter by module if specified
-    let filtered_modules = if let Some(module_name) = request.module {
-        modules
-            .into_iter()
-            .filter(|m| m.name == module_name)
-            .collect()
-    } else {
-        modules
-    };
-
-    let exports_json = json!({
-        "author": request.author,
-        "name": request.name,
-        "version": request.version,
-        "modules": filtered_modules.iter().map(|m| json!({
-            "name": m.name,
-            "unions": m.unions.iter().map(|u| json!({
-                "name": u.name,
-                "args": u.args,
-                "cases": u.cases
-            })).collect::<Vec<_>>(),
-            "aliases": m.aliases.iter().map(|a| json!({
-                "name": a.name,
-                "args": a.args,
-                "type": a.type_annotation
-            })).collect::<Vec<_>>(),
-            "values": m.values.iter().map(|v| json!({
-                "name": v.name,
-                "type": v.type_annotation
-            })).collect::<Vec<_>>(),
-            "binops": m.binops.iter().map(|b| json!({
-                "name": b.name,
-                "type": b.type_annotation,
-                "associativity": b.associativity,
-                "precedence": b.precedence
-            })).collect::<Vec<_>>()
-        })).collect::<Vec<_>>()
-    });
-
-    Ok(CallToolResult {
-        content: vec![CallToolResultContent::Text {
-            text: format!("{}{}", DEPRECATION_WARNING, serde_json::to_string_pretty(&exports_json).unwrap()),
-        }],
-        is_error: false,
-    })
-}
-
-#[derive(Deserialize, Serialize, RpcParams)]
-pub struct GetExportDocsRequest {
-    pub author: String,
-    pub name: String,
-    pub version: String,
-    pub module: String,
-    pub export_name: String,
-}
-
-pub async fn get_export_docs(request: GetExportDocsRequest) -> HandlerResult<CallToolResult> {
-    let package_info = PackageInfo {
-        author: request.author.clone(),
-        name: request.name.clone(),
-        version: request.version.clone(),
-    };
-
-    let modules = fetcher::fetch_docs(&package_info)
-        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-
-    // Find the specific module
-    let module = modules
-        .iter()
-        .find(|m| m.name == request.module)
-        .ok_or_else(|| {
-            json!({"code": -32603, "message": format!("Module '{}' not found", request.module)})
-                .into_handler_error()
-        })?;
-
-    // Search for the export in unions, aliases, values, and binops
-    let mut comment = None;
-    let mut export_type = None;
-    let mut type_annotation = None;
-
-    // Check unions
-    if let Some(union) = module.unions.iter().find(|u| u.name == request.export_name) {
-        comment = Some(union.comment.clone());
-        export_type = Some("union");
-        type_annotation = Some(format!("type {} {}", union.name, union.args.join(" ")));
-    }
-    // Check aliases
-    else if let Some(alias) = module
-        .aliases
-        .iter()
-        .find(|a| a.name == request.export_name)
-    {
-        comment = Some(alias.comment.clone());
-        export_type = Some("alias");
-        type_annotation = Some(format!(
-            "type alias {} {} = {}",
-            alias.name,
-            alias.args.join(" "),
-            alias.type_annotation
-        ));
-    }
-    // Check values
-    else if let Some(value) = module.values.iter().find(|v| v.name == request.export_name) {
-        comment = Some(value.comment.clone());
-        export_type = Some("value");
-        type_annotation = Some(format!("{} : {}", value.name, value.type_annotation));
-    }
-    // Check binops
-    else if let Some(binop) = module.binops.iter().find(|b| b.name == request.export_name) {
-        comment = Some(binop.comment.clone());
-        export_type = Some("binop");
-        type_annotation = Some(format!("({}) : {}", binop.name, binop.type_annotation));
-    }
-
-    if let Some(comment_text) = comment {
-        let result = json!({
-            "author": request.author,
-            "name": request.name,
-            "version": request.version,
-            "module": request.module,
-            "export_name": request.export_name,
-            "export_type": export_type,
-            "type_annotation": type_annotation,
-            "comment": comment_text
-        });
-
-        Ok(CallToolResult {
-            content: vec![CallToolResultContent::Text {
-                text: format!("{}{}", DEPRECATION_WARNING, serde_json::to_string_pretty(&result).unwrap()),
-            }],
-            is_error: false,
-        })
-    } else {
-        Err(json!({
-            "code": -32603,
-            "message": format!("Export '{}' not found in module '{}'", request.export_name, request.module)
-        })
-        .into_handler_error())
-    }
-}
-
-#[derive(Deserialize, Serialize, RpcParams)]
-pub struct SearchPackagesRequest {
-    pub query: String,
-    pub already_included: Option<bool>,
-}
-
-pub async fn search_packages(request: SearchPackagesRequest) -> HandlerResult<CallToolResult> {
-    // Fetch the search index (run blocking HTTP call in separate thread pool)
-    let entries = tokio::task::spawn_blocking(search::fetch_search_index)
-        .await
-        .map_err(|e| {
-            json!({"code": -32603, "message": format!("Task join error: {}", e)})
-                .into_handler_error()
-        })?
-        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
-
-    // Determine if we should exclude packages from elm.json
-    let already_included = request.already_included.unwrap_or(true);
-    let exclude_packages = if !already_included {
-        // Get packages from elm.json to exclude
-        match find_elm_json() {
-            Ok(elm_json_path) => match reader::read_elm_json(&elm_json_path) {
-                Ok(elm_json) => {
-                    let mut excluded = HashSet::new();
-                    for pkg in reader::get_direct_packages(&elm_json) {
-                        excluded.insert(format!("{}/{}", pkg.author, pkg.name));
-                    }
-                    for pkg in reader::get_indirect_packages(&elm_json) {
-                        excluded.insert(format!("{}/{}", pkg.author, pkg.name));
-                    }
-                    Some(excluded)
-                }
-                Err(_) => None,
+            Tool {
+                name: "search_by_type".to_string(),
+                description: Some("Search every package in elm.json (including test-dependencies) for functions, operators and constructors whose type matches a type signature, like Hoogle for Elm. Matching ignores type variable names and module qualification, tolerates reordered arguments, and ranks exact matches before more general or more specific ones.\n\n**Use this when:** User asks 'is there a function that does X' and you can describe X as a type (e.g., '(a -> b) -> List a -> List b', 'String -> Maybe Int').\n\n**Next steps:** Use get_elm_package_export_docs on the best matches to read their documentation.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "signature".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Elm type signature to search for (e.g., '(a -> b) -> List a -> List b', 'Dict comparable v -> List comparable')".to_string()),
+                            enum_values: None,
+                        },
+                        "include_indirect".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Also search indirect dependencies (default: true)".to_string()),
+                            enum_values: None,
+                        },
+                        "limit".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Maximum number of results to return (default: 20)".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["signature".to_string()],
+                },
             },
-            Err(_) => None,
-        }
-    } else {
-        None
-    };
-
-    // Perform fuzzy search
-    let results = search::fuzzy_search(&request.query, &entries, exclude_packages.as_ref(), 20);
-
-    let result = json!({
-        "query": request.query,
-        "results": results,
-        "count": results.len(),
-        "excluded_installed": !already_included
-    });
-
-    Ok(CallToolResult {
-        content: vec![CallToolResultContent::Text {
-            text: format!("{}{}", DEPRECATION_WARNING, serde_json::to_string_pretty(&result).unwrap()),
-        }],
-        is_error: false,
-    })
-}
-
-fn find_elm_json() -> Result<String, String> {
-    let current_dir =
-        env::current_dir().map_err(|e| format!("Failed to get current directory: {e}"))?;
-
-    let mut dir = current_dir.as_path();
-
-    loop {
-        let elm_json_path = dir.join("elm.json");
-        if elm_json_path.exists() {
-            return Ok(elm_json_path.to_string_lossy().to_string());
-        }
-
-        match dir.parent() {
-            Some(parent) => dir = parent,
-            None => {
-                return Err(
-                    "elm.json not found in current directory or any parent directory".to_string(),
-                )
-            }
-        }
+            Tool {
+                name: "get_server_diagnostics".to_string(),
+                description: Some("Report the server's configuration (Elm home, registry URL, cache directory, elm.json location) and in-memory documentation cache statistics (entries, hits, misses, invalidations).\n\n**Use this when:** Documentation lookups fail or seem slow, or the user asks how the server is configured.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {},
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "search_docs".to_string(),
+                description: Some("Full-text search over the documentation of every module, type, alias, function and operator in the project's dependencies (direct, indirect and test). Results are ranked by relevance (BM25) with matching names weighted above comment text, and include a snippet of the matching documentation.\n\n**Use this when:** User asks 'how do I do X' or 'is there something for X' in words rather than types (e.g., 'debounce', 'percent encode', 'parse ISO dates'). One call replaces browsing exports package by package.\n\n**Next steps:** Use get_elm_package_export_docs for the full documentation of a promising result, or search_registry_docs to look beyond the project's dependencies.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "query".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Words describing what you're looking for (e.g., 'debounce', 'percent encode', 'sort by key')".to_string()),
+                            enum_values: None,
+                        },
+                        "include_indirect".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Also search indirect dependencies (default: true)".to_string()),
+                            enum_values: None,
+                        },
+                        "limit".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Maximum number of results to return (default: 10)".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["query".to_string()],
+                },
+            },
+            Tool {
+                name: "get_elm_module_docs".to_string(),
+                description: Some("Render the full documentation of one Elm package module as markdown, the way package.elm-lang.org shows it: the module's prose with each `@docs` line expanded into the signature and doc comment of the listed exports, in the author's intended reading order.\n\n**Use this when:** User wants to read or learn a whole module (e.g., 'explain Json.Decode', 'how is Html.Attributes organized'), or you need the surrounding explanation rather than a single function's docs.\n\n**Workflow:** Use list_installed_packages for the version and get_elm_package_exports for module names, then call this for the module.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "author".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package author (e.g., 'elm'). Get from list_installed_packages.".to_string()),
+                            enum_values: None,
+                        },
+                        "name".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package name (e.g., 'json'). Get from list_installed_packages.".to_string()),
+                            enum_values: None,
+                        },
+                        "version".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package version (e.g., '1.1.3'). Get from list_installed_packages.".to_string()),
+                            enum_values: None,
+                        },
+                        "module".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Module name (e.g., 'Json.Decode'). Get from get_elm_package_exports output.".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![
+                        "author".to_string(),
+                        "name".to_string(),
+                        "version".to_string(),
+                        "module".to_string(),
+                    ],
+                },
+            },
+            Tool {
+                name: "diff_package_api".to_string(),
+                description: Some("Compare the public API of two versions of an Elm package, like `elm diff`. Reports added and removed modules and, per module, added, removed and changed exports (including union constructor and type alias body changes), and classifies the change as MAJOR, MINOR or PATCH. Versions that aren't installed locally are fetched from the package registry.\n\n**Use this when:** User is considering an upgrade ('what changed in elm/http 2.0?', 'is it safe to bump X?') or wants to know what breaks before editing elm.json.\n\n**Workflow:** Use list_installed_packages for the current version, then call this with the version you're considering.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "author".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package author (e.g., 'elm')".to_string()),
+                            enum_values: None,
+                        },
+                        "name".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package name (e.g., 'http')".to_string()),
+                            enum_values: None,
+                        },
+                        "from_version".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("The old version (e.g., '1.0.0'), usually the one in elm.json".to_string()),
+                            enum_values: None,
+                        },
+                        "to_version".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("The new version (e.g., '2.0.0')".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![
+                        "author".to_string(),
+                        "name".to_string(),
+                        "from_version".to_string(),
+                        "to_version".to_string(),
+                    ],
+                },
+            },
+            Tool {
+                name: "dependency_graph".to_string(),
+                description: Some("Build the project's transitive package dependency graph by walking from its direct dependencies through each package's own elm.json. Returns nodes (package, version, where elm.json lists it), and edges with the version constraint each package puts on its dependency and whether the chosen version satisfies it. Can also render the graph as Graphviz DOT or a Mermaid flowchart.\n\n**Use this when:** User asks where an indirect dependency comes from, wants to visualize dependencies, or is untangling version conflicts.\n\n**Next steps:** Use why_dependency to explain one specific indirect package.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "include_test".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Also start from direct test-dependencies (default: false)".to_string()),
+                            enum_values: None,
+                        },
+                        "format".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Output format: 'json' (default), 'dot' for Graphviz, or 'mermaid'".to_string()),
+                            enum_values: Some(vec![
+                                "json".to_string(),
+                                "dot".to_string(),
+                                "mermaid".to_string(),
+                            ]),
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "why_dependency".to_string(),
+                description: Some("Explain why a package is in elm.json: returns every path from a direct dependency down to it, with the version constraint declared at each step.\n\n**Use this when:** elm.json has an unexpected indirect dependency, or the user asks 'why do I have X?' or 'what pulls in X?'.\n\n**Next steps:** Use dependency_graph to see the whole graph.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "package".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Full package name (e.g., 'elm/json'). Get from list_installed_packages with include_indirect.".to_string()),
+                            enum_values: None,
+                        },
+                        "include_test".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Also consider paths from direct test-dependencies (default: true)".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["package".to_string()],
+                },
+            },
+            Tool {
+                name: "plan_install".to_string(),
+                description: Some("Work out what `elm install <package>` would do without running it: solves dependency versions against the package registry and returns the elm.json it would write, with a list of added, changed and removed entries. If no versions work, explains which constraints conflict.\n\n**Use this when:** User wants to add a package and you want to check it is compatible first, or `elm install` reported a conflict.\n\n**Next steps:** Apply the proposed elm.json or run `elm install`; use why_dependency to trace a conflicting package.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "package".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Full package name (e.g., 'elm/http')".to_string()),
+                            enum_values: None,
+                        },
+                        "version".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Exact version to install (default: newest compatible version)".to_string()),
+                            enum_values: None,
+                        },
+                        "snapshot_dir".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Directory holding a registry snapshot (all-packages.json and packages/<author>/<name>/<version>/elm.json) to solve against instead of the live registry".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["package".to_string()],
+                },
+            },
+            Tool {
+                name: "check_dependency_usage".to_string(),
+                description: Some("Scan the project's Elm source files for `import` lines and compare them with elm.json. Reports direct dependencies that are never imported, imports of modules that only an indirect or test dependency provides (the compiler rejects these until the package is a direct dependency), and imports no package provides.\n\n**Use this when:** Cleaning up elm.json, after removing code, or when the compiler says a module can't be found even though its package is in elm.json.\n\n**Next steps:** Remove unused packages with `elm-json uninstall` or by editing elm.json; use plan_install to promote a missing package to a direct dependency.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "find_module".to_string(),
+                description: Some("Find which package provides an Elm module, e.g. `Parser.Advanced` or `Html.Lazy`. Checks the project's dependencies, then every package in the local Elm cache, then likely packages in the registry. Returns each matching package, its version, and whether and where it's listed in elm.json.\n\n**Use this when:** An `import` fails to compile, a compile error mentions a module you don't recognize, or example code imports a module and you need its package.\n\n**Next steps:** If the package isn't a direct dependency, use plan_install to add it; use get_elm_package_exports to see what the module provides.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "module".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Full module name (e.g., 'Json.Decode', 'Parser.Advanced')".to_string()),
+                            enum_values: None,
+                        },
+                        "search_registry".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Check likely packages in the registry when no local package has the module (default: true)".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["module".to_string()],
+                },
+            },
+            Tool {
+                name: "get_project_exports".to_string(),
+                description: Some("Get the exports of the project's own Elm modules, read from the source files in elm.json's source-directories: exposed types, type aliases and values with their type annotations as written, WITHOUT doc comments. Same shape as get_elm_package_exports, plus each module's file path.\n\n**Use this when:** You need to know what an internal module of the project provides, or which project module defines something.\n\n**Next steps:** Use get_project_export_docs for the doc comment of one export.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "module".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Module name (e.g., 'Page.Home'). Omit to list every module in the project, which can be large.".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "get_project_export_docs".to_string(),
+                description: Some("Get the doc comment and declaration of one export of the project's own Elm module, read from source. Same shape as get_elm_package_export_docs.\n\n**Use this when:** You need to understand what a function or type in the project's code does and how to use it.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "module".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Module name (e.g., 'Page.Home')".to_string()),
+                            enum_values: None,
+                        },
+                        "export_name".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Name of the exported function, type or alias".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["module".to_string(), "export_name".to_string()],
+                },
+            },
+            Tool {
+                name: "list_projects".to_string(),
+                description: Some("List the Elm projects in the workspace: every elm.json found below the client's roots (or the --project directory), such as an application, its `review/` config for elm-review, and shared packages. Each project has a name that other tools accept as their `project` argument.\n\n**Use this when:** Working in a monorepo or a repository with more than one elm.json, before asking about the dependencies of a specific one.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {},
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "license_report".to_string(),
+                description: Some("Audit the licenses of every package in the project's dependency tree. Reads the `license` field of each package's elm.json (falling back to the registry's search index for packages whose elm.json can't be read), groups packages by SPDX identifier, and flags packages whose license is on a deny-list. Returns JSON, or a markdown table ready to paste into a document.\n\n**Use this when:** User asks which licenses their dependencies use, needs a license report for legal review, or wants to check for copyleft licenses before shipping.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "include_test".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Also include test-dependencies and their dependencies (default: false)".to_string()),
+                            enum_values: None,
+                        },
+                        "deny".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Comma-separated SPDX identifiers to flag, e.g. 'GPL-*, AGPL-3.0-only'. A trailing '*' matches any suffix. Defaults to the ELM_PACKAGE_MCP_LICENSE_DENY environment variable.".to_string()),
+                            enum_values: None,
+                        },
+                        "format".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Output format: 'json' (default) or 'markdown' for tables".to_string()),
+                            enum_values: Some(vec![
+                                "json".to_string(),
+                                "markdown".to_string(),
+                            ]),
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![],
+                },
+            },
+            Tool {
+                name: "get_elm_package_info".to_string(),
+                description: Some("Get an Elm package's metadata from its own elm.json: summary, license, exposed modules (with the headings the package website groups them under), the range of Elm versions it supports, and the version ranges of its dependencies. Read from the local Elm cache, or from the package registry when the package isn't installed.\n\n**Use this when:** User asks what a package is for, which license it uses, which modules it exposes, or what it depends on, before reading its README or exports.\n\n**Next steps:** Use get_elm_module_docs to read one of its modules.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "author".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package author (e.g., 'elm')".to_string()),
+                            enum_values: None,
+                        },
+                        "name".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package name (e.g., 'core')".to_string()),
+                            enum_values: None,
+                        },
+                        "version".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package version (e.g., '1.0.5'). Get from list_installed_packages or search_packages.".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec![
+                        "author".to_string(),
+                        "name".to_string(),
+                        "version".to_string(),
+                    ],
+                },
+            },
+            Tool {
+                name: "get_package_releases".to_string(),
+                description: Some("List every published version of an Elm package with its publish date, from the registry's releases.json. When the project uses the package, reports which version it's on and how far behind the latest release it is (number of newer releases, whether upgrading is a MAJOR, MINOR or PATCH change, and how many days apart they were published).\n\n**Use this when:** User asks whether a dependency is outdated, when a version was released, or how actively a package is maintained.\n\n**Next steps:** Use diff_package_api to see what changed between the project's version and the latest; use plan_install to preview upgrading.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "author".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package author (e.g., 'elm')".to_string()),
+                            enum_values: None,
+                        },
+                        "name".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Package name (e.g., 'json')".to_string()),
+                            enum_values: None,
+                        },
+                        "project".to_string() => project_property()
+                    },
+                    required: vec!["author".to_string(), "name".to_string()],
+                },
+            },
+            Tool {
+                name: "search_registry_docs".to_string(),
+                description: Some("Full-text search over module names, export names and doc comments of published Elm packages, not just the project's dependencies. Searches a local mirror of the registry's docs, which is first brought up to date by downloading docs for packages that are new or updated since the last sync, at most `max_downloads` (default: 200) per call. The whole registry takes several calls to mirror: the response's `coverage` says how many published packages the mirror holds and how many are still `remaining`; call again to download more. Pass `sync: false` to search the mirror as it is, offline.\n\n**Use this when:** Nothing in the project's dependencies does what's needed (search_docs came up empty) and you want to find a package by what its functions do (e.g., 'a function that parses ISO-8601', 'levenshtein distance').\n\n**Next steps:** Use get_package_releases or get_elm_package_info to vet a result, then plan_install to add it.".to_string()),
+                input_schema: ToolInputSchema {
+                    type_name: "object".to_string(),
+                    properties: hashmap! {
+                        "query".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("string".to_string()),
+                            description: Some("Words describing what you're looking for (e.g., 'parse ISO-8601 dates', 'levenshtein')".to_string()),
+                            enum_values: None,
+                        },
+                        "limit".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Maximum number of results to return (default: 10)".to_string()),
+                            enum_values: None,
+                        },
+                        "sync".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("boolean".to_string()),
+                            description: Some("Update the mirror from the registry before searching, downloading only new and updated packages (default: true). Set to false to search offline.".to_string()),
+                            enum_values: None,
+                        },
+                        "max_downloads".to_string() => ToolInputSchemaProperty {
+                            type_name: Some("integer".to_string()),
+                            description: Some("Maximum number of docs.json files to download when syncing (default: 200). The response's `coverage.remaining` says how many are left for the next call.".to_string()),
+                            enum_values: None,
+                        }
+                    },
+                    required: vec!["query".to_string()],
+                },
+            },
+        ],
+        next_cursor: None,
+    };
+    Ok(response)
+}
+
+/// Which elm.json dependency groups list_installed_packages reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    Dependencies,
+    Test,
+    All,
+}
+
+/// The `project` argument shared by every tool that reads elm.json
+fn project_property() -> ToolInputSchemaProperty {
+    ToolInputSchemaProperty {
+        type_name: Some("string".to_string()),
+        description: Some("Which project's elm.json to use in a workspace with several, by name or directory (see list_projects). Defaults to the project the server was started in.".to_string()),
+        enum_values: None,
+    }
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct ListInstalledRequest {
+    pub include_indirect: Option<bool>,
+    pub scope: Option<Scope>,
+    pub project: Option<String>,
+}
+
+pub async fn list_installed(request: ListInstalledRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let include_indirect = request.include_indirect.unwrap_or(false);
+    let scope = request.scope.unwrap_or_default();
+    let elm_version = reader::get_elm_version(&elm_json);
+
+    let direct_count = reader::get_direct_dependencies(&elm_json).len();
+    let indirect_count = reader::get_indirect_dependencies(&elm_json).len();
+    let test_count = reader::get_test_dependencies(&elm_json).len();
+
+    let dependencies: Vec<(reader::Section, reader::Dependency)> = reader::Section::ALL
+        .into_iter()
+        .filter(|section| match scope {
+            Scope::Dependencies => !section.is_test(),
+            Scope::Test => section.is_test(),
+            Scope::All => true,
+        })
+        .filter(|section| include_indirect || !section.is_indirect())
+        .flat_map(|section| {
+            reader::get_dependencies(&elm_json, section)
+                .into_iter()
+                .map(move |dependency| (section, dependency))
+        })
+        .collect();
+
+    let package_list: Vec<Value> = run_blocking(move || {
+        Ok(dependencies
+            .iter()
+            .map(|(section, dependency)| {
+                let p = dependency.resolve(elm_version.as_deref());
+                let mut entry = json!({
+                    "author": p.author,
+                    "name": p.name,
+                    "version": p.version,
+                    "type": section
+                });
+                // Package projects declare ranges; show what was resolved from
+                if !dependency.constraint.is_exact() {
+                    entry["constraint"] = json!(dependency.constraint);
+                }
+                entry
+            })
+            .collect())
+    })
+    .await?;
+
+    let result = json!({
+        "project_type": reader::get_project_type(&elm_json),
+        "packages": package_list,
+        "total": package_list.len(),
+        "direct_count": direct_count,
+        "indirect_count": indirect_count,
+        "test_count": test_count
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetReadmeRequest {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_readme(request: GetReadmeRequest) -> HandlerResult<CallToolResult> {
+    let package_info = PackageInfo {
+        author: request.author,
+        name: request.name,
+        version: request.version,
+    };
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let readme =
+        run_blocking(move || fetcher::fetch_readme(&package_info, elm_version.as_deref())).await?;
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!("{}{}", DEPRECATION_WARNING, readme),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetExportsRequest {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+    pub module: Option<String>,
+    pub project: Option<String>,
+}
+
+pub async fn get_exports(request: GetExportsRequest) -> HandlerResult<CallToolResult> {
+    let package_info = PackageInfo {
+        author: request.author.clone(),
+        name: request.name.clone(),
+        version: request.version.clone(),
+    };
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let modules =
+        run_blocking(move || fetcher::fetch_docs(&package_info, elm_version.as_deref())).await?;
+
+    // Filter by module if specified
+    let filtered_modules: Vec<&fetcher::Module> = modules
+        .iter()
+        .filter(|m| request.module.as_ref().is_none_or(|name| &m.name == name))
+        .collect();
+
+    let exports_json = json!({
+        "author": request.author,
+        "name": request.name,
+        "version": request.version,
+        "modules": filtered_modules.iter().map(|m| module_exports_json(m)).collect::<Vec<_>>()
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&exports_json).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetExportDocsRequest {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+    pub module: String,
+    pub export_name: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_export_docs(request: GetExportDocsRequest) -> HandlerResult<CallToolResult> {
+    let package_info = PackageInfo {
+        author: request.author.clone(),
+        name: request.name.clone(),
+        version: request.version.clone(),
+    };
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let modules =
+        run_blocking(move || fetcher::fetch_docs(&package_info, elm_version.as_deref())).await?;
+
+    // Find the specific module
+    let module = modules
+        .iter()
+        .find(|m| m.name == request.module)
+        .ok_or_else(|| {
+            json!({"code": -32603, "message": format!("Module '{}' not found", request.module)})
+                .into_handler_error()
+        })?;
+
+    let found = find_export(module, &request.export_name);
+    if let Some((export_type, type_annotation, comment_text)) = found {
+        let result = json!({
+            "author": request.author,
+            "name": request.name,
+            "version": request.version,
+            "module": request.module,
+            "export_name": request.export_name,
+            "export_type": export_type,
+            "type_annotation": type_annotation,
+            "comment": comment_text
+        });
+
+        Ok(CallToolResult {
+            content: vec![CallToolResultContent::Text {
+                text: format!(
+                    "{}{}",
+                    DEPRECATION_WARNING,
+                    serde_json::to_string_pretty(&result).unwrap()
+                ),
+            }],
+            is_error: false,
+        })
+    } else {
+        Err(json!({
+            "code": -32603,
+            "message": format!("Export '{}' not found in module '{}'", request.export_name, request.module)
+        })
+        .into_handler_error())
+    }
+}
+
+/// The exports of a module without their doc comments, as listed by
+/// get_elm_package_exports
+fn module_exports_json(m: &fetcher::Module) -> Value {
+    json!({
+        "name": m.name,
+        "unions": m.unions.iter().map(|u| json!({
+            "name": u.name,
+            "args": u.args,
+            "cases": u.cases
+        })).collect::<Vec<_>>(),
+        "aliases": m.aliases.iter().map(|a| json!({
+            "name": a.name,
+            "args": a.args,
+            "type": a.type_annotation
+        })).collect::<Vec<_>>(),
+        "values": m.values.iter().map(|v| json!({
+            "name": v.name,
+            "type": v.type_annotation
+        })).collect::<Vec<_>>(),
+        "binops": m.binops.iter().map(|b| json!({
+            "name": b.name,
+            "type": b.type_annotation,
+            "associativity": b.associativity,
+            "precedence": b.precedence
+        })).collect::<Vec<_>>()
+    })
+}
+
+/// Find an export in a module's unions, aliases, values and binops, returning
+/// its kind, its declaration and its doc comment
+fn find_export(module: &fetcher::Module, name: &str) -> Option<(&'static str, String, String)> {
+    if let Some(union) = module.unions.iter().find(|u| u.name == name) {
+        Some((
+            "union",
+            format!("type {} {}", union.name, union.args.join(" ")),
+            union.comment.clone(),
+        ))
+    } else if let Some(alias) = module.aliases.iter().find(|a| a.name == name) {
+        Some((
+            "alias",
+            format!(
+                "type alias {} {} = {}",
+                alias.name,
+                alias.args.join(" "),
+                alias.type_annotation
+            ),
+            alias.comment.clone(),
+        ))
+    } else if let Some(value) = module.values.iter().find(|v| v.name == name) {
+        Some((
+            "value",
+            format!("{} : {}", value.name, value.type_annotation),
+            value.comment.clone(),
+        ))
+    } else {
+        module.binops.iter().find(|b| b.name == name).map(|binop| {
+            (
+                "binop",
+                format!("({}) : {}", binop.name, binop.type_annotation),
+                binop.comment.clone(),
+            )
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct SearchPackagesRequest {
+    pub query: String,
+    pub already_included: Option<bool>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub project: Option<String>,
+}
+
+pub async fn search_packages(request: SearchPackagesRequest) -> HandlerResult<CallToolResult> {
+    // Searching works without a project, but not with a misnamed one
+    let elm_json = project_elm_json(request.project.as_deref())?;
+
+    // Fetch the search index (run blocking HTTP call in separate thread pool)
+    let index = run_blocking(search::fetch_search_index).await?;
+
+    // Exclude packages already in elm.json if asked to
+    let already_included = request.already_included.unwrap_or(true);
+    let exclude_packages = elm_json.filter(|_| !already_included).map(|elm_json| {
+        reader::get_packages(&elm_json, &reader::Section::ALL)
+            .into_iter()
+            .map(|pkg| format!("{}/{}", pkg.author, pkg.name))
+            .collect::<HashSet<_>>()
+    });
+
+    let query = search::SearchQuery::parse(&request.query);
+    let popularity = run_blocking(|| Ok(ranking::snapshot_popularity())).await?;
+    let matches = search::search(
+        &query,
+        &index.entries,
+        exclude_packages.as_ref(),
+        popularity,
+    );
+    let total = matches.len();
+    let offset = request.offset.unwrap_or(0);
+    let results: Vec<search::SearchResult> = matches
+        .into_iter()
+        .skip(offset)
+        .take(request.limit.unwrap_or(20))
+        .collect();
+
+    let result = json!({
+        "query": request.query,
+        "results": results,
+        "count": results.len(),
+        "total": total,
+        "offset": offset,
+        "excluded_installed": !already_included,
+        "stale": index.stale,
+        "index_fetched_at": chrono::DateTime::from_timestamp(index.fetched_at, 0)
+            .map(|t| t.to_rfc3339())
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct SearchByTypeRequest {
+    pub signature: String,
+    pub include_indirect: Option<bool>,
+    pub limit: Option<usize>,
+    pub project: Option<String>,
+}
+
+pub async fn search_by_type(request: SearchByTypeRequest) -> HandlerResult<CallToolResult> {
+    let query = annotation::parse(&request.signature).map_err(|e| {
+        json!({"code": -32602, "message": format!("Invalid type signature: {}", e)})
+            .into_handler_error()
+    })?;
+
+    let signature = query.to_string();
+
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let elm_version = reader::get_elm_version(&elm_json);
+    let packages = project_packages(&elm_json, request.include_indirect.unwrap_or(true));
+
+    let (mut matches, skipped) = run_blocking(move || {
+        let mut matches = Vec::new();
+        let mut skipped = Vec::new();
+        for package in &packages {
+            match fetcher::fetch_docs(package, elm_version.as_deref()) {
+                Ok(modules) => {
+                    matches.extend(type_search::search_modules(&query, package, &modules))
+                }
+                Err(e) => skipped.push(json!({
+                    "package": format!("{}/{}", package.author, package.name),
+                    "version": package.version,
+                    "reason": e
+                })),
+            }
+        }
+        Ok((matches, skipped))
+    })
+    .await?;
+
+    // Closest matches first, preferring shorter names among equally close matches
+    matches.sort_by(|a, b| {
+        a.distance
+            .cmp(&b.distance)
+            .then_with(|| a.name.len().cmp(&b.name.len()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    matches.truncate(request.limit.unwrap_or(20));
+
+    let result = json!({
+        "signature": signature,
+        "results": matches,
+        "count": matches.len(),
+        "skipped": skipped
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetDiagnosticsRequest {}
+
+pub async fn get_diagnostics(
+    _request: Option<GetDiagnosticsRequest>,
+) -> HandlerResult<CallToolResult> {
+    let elm_json_path = find_elm_json();
+    let elm_version = project_elm_version(None)?;
+    let elm_home = home::elm_home();
+
+    let result = json!({
+        "elm_json": elm_json_path.as_ref().ok(),
+        "elm_json_error": elm_json_path.as_ref().err(),
+        "project_override": project::project_override().map(|p| p.display().to_string()),
+        "roots": project::roots()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>(),
+        "elm_version": elm_version,
+        "elm_home": elm_home.as_ref().ok().map(|p| p.display().to_string()),
+        "package_roots": elm_home.as_ref().ok().map(|elm_home| {
+            home::package_roots(elm_home, elm_version.as_deref())
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+        }),
+        "registry_url": registry::base_url(),
+        "cache_dir": cache::cache_dir().ok().map(|p| p.display().to_string()),
+        "cache_ttl_seconds": cache::ttl().as_secs(),
+        "docs_cache": docs_cache::stats()
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct SearchDocsRequest {
+    pub query: String,
+    pub include_indirect: Option<bool>,
+    pub limit: Option<usize>,
+    pub project: Option<String>,
+}
+
+pub async fn search_docs(request: SearchDocsRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let elm_version = reader::get_elm_version(&elm_json);
+    let packages = project_packages(&elm_json, request.include_indirect.unwrap_or(true));
+
+    let (index, skipped) = run_blocking(move || {
+        let mut index = doc_search::DocIndex::new();
+        let mut skipped = Vec::new();
+        for package in &packages {
+            match fetcher::fetch_docs(package, elm_version.as_deref()) {
+                Ok(modules) => index.add_package(package, &modules),
+                Err(e) => skipped.push(json!({
+                    "package": format!("{}/{}", package.author, package.name),
+                    "version": package.version,
+                    "reason": e
+                })),
+            }
+        }
+        Ok((index, skipped))
+    })
+    .await?;
+
+    let results = index.search(&request.query, request.limit.unwrap_or(10));
+
+    let result = json!({
+        "query": request.query,
+        "results": results,
+        "count": results.len(),
+        "indexed_documents": index.len(),
+        "skipped": skipped
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetModuleDocsRequest {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+    pub module: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_module_docs(request: GetModuleDocsRequest) -> HandlerResult<CallToolResult> {
+    let package_info = PackageInfo {
+        author: request.author.clone(),
+        name: request.name.clone(),
+        version: request.version.clone(),
+    };
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let modules =
+        run_blocking(move || fetcher::fetch_docs(&package_info, elm_version.as_deref())).await?;
+
+    let module = modules
+        .iter()
+        .find(|m| m.name == request.module)
+        .ok_or_else(|| {
+            let available: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+            json!({
+                "code": -32603,
+                "message": format!(
+                    "Module '{}' not found in {}/{} {}. Available modules: {}",
+                    request.module,
+                    request.author,
+                    request.name,
+                    request.version,
+                    available.join(", ")
+                )
+            })
+            .into_handler_error()
+        })?;
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!("{}{}", DEPRECATION_WARNING, module_docs::render(module)),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct DiffPackageApiRequest {
+    pub author: String,
+    pub name: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub project: Option<String>,
+}
+
+pub async fn diff_package_api(request: DiffPackageApiRequest) -> HandlerResult<CallToolResult> {
+    let package = |version: &str| PackageInfo {
+        author: request.author.clone(),
+        name: request.name.clone(),
+        version: version.to_string(),
+    };
+    let (from, to) = (package(&request.from_version), package(&request.to_version));
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let diff = run_blocking(move || {
+        let old = fetcher::fetch_docs(&from, elm_version.as_deref())?;
+        let new = fetcher::fetch_docs(&to, elm_version.as_deref())?;
+        Ok(api_diff::diff(&old, &new))
+    })
+    .await?;
+
+    let result = json!({
+        "package": format!("{}/{}", request.author, request.name),
+        "from_version": request.from_version,
+        "to_version": request.to_version,
+        "magnitude": diff.magnitude,
+        "added_modules": diff.added_modules,
+        "removed_modules": diff.removed_modules,
+        "changed_modules": diff.changed_modules
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+/// Output formats for dependency_graph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Dot,
+    Mermaid,
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct DependencyGraphRequest {
+    pub include_test: Option<bool>,
+    pub format: Option<GraphFormat>,
+    pub project: Option<String>,
+}
+
+pub async fn dependency_graph(request: DependencyGraphRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let include_test = request.include_test.unwrap_or(false);
+    let graph =
+        run_blocking(move || Ok(graph::DependencyGraph::for_project(&elm_json, include_test)))
+            .await?;
+
+    let text = match request.format.unwrap_or_default() {
+        GraphFormat::Json => serde_json::to_string_pretty(&graph).unwrap(),
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Mermaid => graph.to_mermaid(),
+    };
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!("{}{}", DEPRECATION_WARNING, text),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct WhyDependencyRequest {
+    pub package: String,
+    pub include_test: Option<bool>,
+    pub project: Option<String>,
+}
+
+pub async fn why_dependency(request: WhyDependencyRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let include_test = request.include_test.unwrap_or(true);
+    let graph =
+        run_blocking(move || Ok(graph::DependencyGraph::for_project(&elm_json, include_test)))
+            .await?;
+
+    let node = graph.node(&request.package).ok_or_else(|| {
+        json!({
+            "code": -32603,
+            "message": format!(
+                "Package '{}' is not in the project's dependency graph. Use list_installed_packages to see the project's packages.",
+                request.package
+            )
+        })
+        .into_handler_error()
+    })?;
+    let paths = graph.paths_to(&request.package);
+
+    // Packages whose own elm.json couldn't be read hide any paths through them
+    let unreadable: Vec<&graph::Node> = graph.nodes.iter().filter(|n| n.error.is_some()).collect();
+
+    let result = json!({
+        "package": node.package,
+        "version": node.version,
+        "type": node.section,
+        "direct": graph.roots.contains(&node.package),
+        "paths": paths.paths,
+        "count": paths.paths.len(),
+        "truncated": paths.truncated,
+        "unreadable": unreadable
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct PlanInstallRequest {
+    pub package: String,
+    pub version: Option<String>,
+    pub snapshot_dir: Option<String>,
+    pub project: Option<String>,
+}
+
+pub async fn plan_install(request: PlanInstallRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let version = request
+        .version
+        .as_deref()
+        .map(version::Version::parse)
+        .transpose()
+        .map_err(|e| json!({"code": -32602, "message": e}).into_handler_error())?;
+
+    let snapshot_dir = request.snapshot_dir.map(std::path::PathBuf::from);
+    let elm_version = reader::get_elm_version(&elm_json);
+    let plan = run_blocking(move || {
+        let registry = snapshot::load_registry(snapshot_dir, elm_version)?;
+        solver::plan_install(&elm_json, &request.package, version, registry.as_ref())
+    })
+    .await?;
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&plan).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct CheckDependencyUsageRequest {
+    pub project: Option<String>,
+}
+
+pub async fn check_dependency_usage(
+    request: Option<CheckDependencyUsageRequest>,
+) -> HandlerResult<CallToolResult> {
+    let project = request.and_then(|r| r.project);
+    let elm_json_path = project::elm_json_for(project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let project_dir = project_dir(&elm_json_path);
+
+    let usage = run_blocking(move || usage::for_project(&elm_json, &project_dir)).await?;
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&usage).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct FindModuleRequest {
+    pub module: String,
+    pub search_registry: Option<bool>,
+    pub project: Option<String>,
+}
+
+pub async fn find_module(request: FindModuleRequest) -> HandlerResult<CallToolResult> {
+    // Lookups work without a project; elm.json only adds where packages are listed
+    let elm_json = project_elm_json(request.project.as_deref())?;
+    let search_registry = request.search_registry.unwrap_or(true);
+
+    let lookup = run_blocking(move || {
+        Ok(module_index::find_module(
+            &request.module,
+            elm_json.as_ref(),
+            search_registry,
+        ))
+    })
+    .await?;
+
+    let result = json!({
+        "module": lookup.module,
+        "found": !lookup.matches.is_empty(),
+        "matches": lookup.matches,
+        "searched_registry": lookup.searched_registry,
+        "registry_error": lookup.registry_error
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetProjectExportsRequest {
+    pub module: Option<String>,
+    pub project: Option<String>,
+}
+
+pub async fn get_project_exports(
+    request: GetProjectExportsRequest,
+) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let project_dir = project_dir(&elm_json_path);
+
+    let modules = run_blocking(move || match &request.module {
+        Some(name) => Ok(vec![project_docs::project_module(
+            &elm_json,
+            &project_dir,
+            name,
+        )?]),
+        None => project_docs::project_modules(&elm_json, &project_dir),
+    })
+    .await?;
+
+    let exports_json = json!({
+        "modules": modules.iter().map(|m| {
+            let mut module = module_exports_json(&m.module);
+            module["path"] = json!(m.path);
+            module
+        }).collect::<Vec<_>>()
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&exports_json).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetProjectExportDocsRequest {
+    pub module: String,
+    pub export_name: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_project_export_docs(
+    request: GetProjectExportDocsRequest,
+) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let project_dir = project_dir(&elm_json_path);
+
+    let name = request.module.clone();
+    let project_module =
+        run_blocking(move || project_docs::project_module(&elm_json, &project_dir, &name)).await?;
+
+    let (export_type, type_annotation, comment) =
+        find_export(&project_module.module, &request.export_name).ok_or_else(|| {
+            json!({
+                "code": -32603,
+                "message": format!(
+                    "Export '{}' not found in module '{}'. Only exposed declarations are listed.",
+                    request.export_name, request.module
+                )
+            })
+            .into_handler_error()
+        })?;
+
+    let result = json!({
+        "module": request.module,
+        "path": project_module.path,
+        "export_name": request.export_name,
+        "export_type": export_type,
+        "type_annotation": type_annotation,
+        "comment": comment
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct ListProjectsRequest {}
+
+pub async fn list_projects(_request: Option<ListProjectsRequest>) -> HandlerResult<CallToolResult> {
+    let default = find_elm_json().ok();
+    let projects = run_blocking(move || {
+        Ok(project::projects()
+            .into_iter()
+            .map(|p| {
+                let elm_json_path = p.elm_json.to_string_lossy().to_string();
+                let mut entry = json!({
+                    "name": p.name,
+                    "elm_json": elm_json_path,
+                    "default": default.as_deref() == Some(elm_json_path.as_str())
+                });
+                match reader::read_elm_json(&elm_json_path) {
+                    Ok(elm_json) => {
+                        entry["type"] = json!(reader::get_project_type(&elm_json));
+                        entry["elm_version"] = json!(reader::get_elm_version(&elm_json));
+                    }
+                    Err(e) => entry["error"] = json!(e),
+                }
+                entry
+            })
+            .collect::<Vec<Value>>())
+    })
+    .await?;
+
+    let result = json!({
+        "projects": projects,
+        "count": projects.len()
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+/// Output formats for license_report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Markdown,
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct LicenseReportRequest {
+    pub include_test: Option<bool>,
+    pub deny: Option<String>,
+    pub format: Option<ReportFormat>,
+    pub project: Option<String>,
+}
+
+pub async fn license_report(request: LicenseReportRequest) -> HandlerResult<CallToolResult> {
+    let elm_json_path = project::elm_json_for(request.project.as_deref())
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let elm_json = reader::read_elm_json(&elm_json_path)
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+
+    let include_test = request.include_test.unwrap_or(false);
+    let deny_list = match &request.deny {
+        Some(deny) => license::parse_deny_list(deny),
+        None => license::deny_list_from_env(),
+    };
+    let report =
+        run_blocking(move || Ok(license::for_project(&elm_json, include_test, &deny_list))).await?;
+
+    let text = match request.format.unwrap_or_default() {
+        ReportFormat::Json => serde_json::to_string_pretty(&report).unwrap(),
+        ReportFormat::Markdown => report.to_markdown(),
+    };
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!("{}{}", DEPRECATION_WARNING, text),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetPackageInfoRequest {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_package_info(request: GetPackageInfoRequest) -> HandlerResult<CallToolResult> {
+    let package_info = PackageInfo {
+        author: request.author,
+        name: request.name,
+        version: request.version,
+    };
+    fetcher::validate_package(&package_info)
+        .map_err(|e| json!({"code": -32602, "message": e}).into_handler_error())?;
+
+    let elm_version = project_elm_version(request.project.as_deref())?;
+    let elm_json =
+        run_blocking(move || fetcher::fetch_elm_json(&package_info, elm_version.as_deref()))
+            .await?;
+    let metadata = reader::get_package_metadata(&elm_json);
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&metadata).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetPackageReleasesRequest {
+    pub author: String,
+    pub name: String,
+    pub project: Option<String>,
+}
+
+pub async fn get_package_releases(
+    request: GetPackageReleasesRequest,
+) -> HandlerResult<CallToolResult> {
+    registry::validate_package_name(&request.author, &request.name)
+        .map_err(|e| json!({"code": -32602, "message": e}).into_handler_error())?;
+    let package = format!("{}/{}", request.author, request.name);
+
+    let elm_json = project_elm_json(request.project.as_deref())?;
+    let listed = elm_json.as_ref().and_then(|elm_json| {
+        let elm_version = reader::get_elm_version(elm_json);
+        reader::Section::ALL.into_iter().find_map(|section| {
+            reader::get_dependencies(elm_json, section)
+                .into_iter()
+                .find(|d| format!("{}/{}", d.author, d.name) == package)
+                .map(|d| (section, d.resolve(elm_version.as_deref()).version))
+        })
+    });
+    let current = listed
+        .as_ref()
+        .and_then(|(_, version)| version::Version::parse(version).ok());
+
+    let name = package.clone();
+    let releases = run_blocking(move || releases::fetch_releases(&name)).await?;
+    let history = releases::history(&package, releases, current);
+
+    let mut result = json!(history);
+    result["type"] = json!(listed.map(|(section, _)| section));
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct SearchRegistryDocsRequest {
+    pub query: String,
+    pub limit: Option<usize>,
+    pub sync: Option<bool>,
+    pub max_downloads: Option<usize>,
+}
+
+pub async fn search_registry_docs(
+    request: SearchRegistryDocsRequest,
+) -> HandlerResult<CallToolResult> {
+    let sync = request.sync.unwrap_or(true);
+    let max_downloads = request
+        .max_downloads
+        .unwrap_or(docs_mirror::DEFAULT_MAX_DOWNLOADS);
+
+    let (mirror, report, sync_error, coverage, index) = run_blocking(move || {
+        let mirror = docs_mirror::DocsMirror::from_env()?;
+        // Without the search index the mirror can't be synced, but what it
+        // already holds is still searchable
+        let (search_index, index_error) = match search::fetch_search_index() {
+            Ok(search_index) => (Some(search_index), None),
+            Err(e) => (None, Some(e)),
+        };
+        let entries = search_index.as_ref().map(|index| index.entries.as_slice());
+
+        let (report, sync_error) = match entries {
+            Some(entries) if sync => {
+                match mirror.sync(&registry::base_url(), entries, Some(max_downloads)) {
+                    Ok(report) => (Some(report), None),
+                    Err(e) => (None, Some(e)),
+                }
+            }
+            _ if sync => (None, index_error),
+            _ => (None, None),
+        };
+        let coverage = mirror.coverage(entries)?;
+        let index = mirror.index()?;
+        Ok((mirror, report, sync_error, coverage, index))
+    })
+    .await?;
+
+    let manifest = mirror
+        .manifest()
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())?;
+    let results = index.search(&request.query, request.limit.unwrap_or(10));
+
+    let result = json!({
+        "query": request.query,
+        "results": results,
+        "count": results.len(),
+        "mirror": {
+            "dir": mirror.dir().display().to_string(),
+            "packages": manifest.packages.len(),
+            "indexed_documents": index.len(),
+            "synced_at": manifest
+                .synced_at
+                .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                .map(|t| t.to_rfc3339())
+        },
+        "coverage": coverage,
+        "sync": report,
+        "sync_error": sync_error
+    });
+
+    Ok(CallToolResult {
+        content: vec![CallToolResultContent::Text {
+            text: format!(
+                "{}{}",
+                DEPRECATION_WARNING,
+                serde_json::to_string_pretty(&result).unwrap()
+            ),
+        }],
+        is_error: false,
+    })
+}
+
+/// Every package whose docs a search over the project should cover: direct
+/// and test dependencies, plus their indirect dependencies if requested
+fn project_packages(elm_json: &Value, include_indirect: bool) -> Vec<PackageInfo> {
+    let sections: Vec<reader::Section> = reader::Section::ALL
+        .into_iter()
+        .filter(|section| include_indirect || !section.is_indirect())
+        .collect();
+    reader::get_packages(elm_json, &sections)
+}
+
+/// The elm.json of the named or default project, if there is one. Lookups
+/// that work without a project don't fail over a missing or unreadable
+/// default elm.json, but a `project` that doesn't resolve is an error.
+fn project_elm_json(project: Option<&str>) -> HandlerResult<Option<Value>> {
+    match project::elm_json_for(project) {
+        Ok(path) => Ok(reader::read_elm_json(&path).ok()),
+        Err(e) if project.is_some() => {
+            Err(json!({"code": -32603, "message": e}).into_handler_error())
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// The `elm-version` of the named or default project, if there is one. See
+/// [`project_elm_json`].
+fn project_elm_version(project: Option<&str>) -> HandlerResult<Option<String>> {
+    Ok(project_elm_json(project)?.and_then(|elm_json| reader::get_elm_version(&elm_json)))
+}
+
+/// The directory holding the project's elm.json
+fn project_dir(elm_json_path: &str) -> std::path::PathBuf {
+    std::path::Path::new(elm_json_path)
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default()
+}
+
+/// Run blocking file or HTTP access on tokio's blocking thread pool
+async fn run_blocking<T, F>(f: F) -> HandlerResult<T>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| {
+            json!({"code": -32603, "message": format!("Task join error: {}", e)})
+                .into_handler_error()
+        })?
+        .map_err(|e| json!({"code": -32603, "message": e}).into_handler_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unknown_project_is_an_error() {
+        let project = Some("no-such-project".to_string());
+        assert!(project_elm_json(project.as_deref()).is_err());
+
+        let search = search_packages(SearchPackagesRequest {
+            query: "json".to_string(),
+            already_included: Some(false),
+            limit: None,
+            offset: None,
+            project: project.clone(),
+        })
+        .await;
+        assert!(search.is_err());
+
+        let lookup = find_module(FindModuleRequest {
+            module: "Json.Decode".to_string(),
+            search_registry: Some(false),
+            project,
+        })
+        .await;
+        assert!(lookup.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_package_info_rejects_invalid_package() {
+        let info = |author: &str, name: &str, version: &str| {
+            get_package_info(GetPackageInfoRequest {
+                author: author.to_string(),
+                name: name.to_string(),
+                version: version.to_string(),
+                project: None,
+            })
+        };
+        assert!(info("..", "core", "1.0.5").await.is_err());
+        assert!(info("elm", "core/../..", "1.0.5").await.is_err());
+        assert!(info("elm", "core", "latest").await.is_err());
     }
 }
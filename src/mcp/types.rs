@@ -298,8 +298,9 @@ pub struct ListRootsResult {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Root {
-    pub name: String,
-    pub url: String,
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 // ----- json-rpc -----
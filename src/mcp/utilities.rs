@@ -1,8 +1,11 @@
+use crate::mcp::project;
 use crate::mcp::types::*;
 use crate::mcp::{PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION};
 use clap::Parser;
 use rpc_router::HandlerResult;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use url::Url;
 
 #[derive(Parser, Debug)]
 #[command(name = SERVER_NAME)]
@@ -24,6 +27,11 @@ pub struct Args {
     /// Output as JSON
     #[arg(long)]
     pub json: bool,
+
+    /// Project directory (or its elm.json) to use, instead of looking for
+    /// elm.json in the client's workspace roots or the working directory
+    #[arg(long, value_name = "PATH")]
+    pub project: Option<PathBuf>,
 }
 
 pub fn display_info(args: &Args) {
@@ -71,6 +79,74 @@ pub fn display_info(args: &Args) {
                 {
                     "name": "get_elm_package_export_docs",
                     "description": "Get documentation for a specific export in an Elm package module"
+                },
+                {
+                    "name": "search_by_type",
+                    "description": "Search installed packages for exports matching a type signature"
+                },
+                {
+                    "name": "get_server_diagnostics",
+                    "description": "Show server configuration and documentation cache statistics"
+                },
+                {
+                    "name": "search_docs",
+                    "description": "Full-text search over the documentation of all dependencies"
+                },
+                {
+                    "name": "get_elm_module_docs",
+                    "description": "Render a package module's documentation as markdown with @docs expanded"
+                },
+                {
+                    "name": "diff_package_api",
+                    "description": "Compare the API of two versions of a package and classify the change as MAJOR/MINOR/PATCH"
+                },
+                {
+                    "name": "dependency_graph",
+                    "description": "Build the transitive dependency graph from cached package elm.json files, as JSON, DOT or Mermaid"
+                },
+                {
+                    "name": "why_dependency",
+                    "description": "Explain why a package is in elm.json with every path from a direct dependency to it"
+                },
+                {
+                    "name": "plan_install",
+                    "description": "Preview the elm.json changes `elm install` would make, or explain the version conflict"
+                },
+                {
+                    "name": "check_dependency_usage",
+                    "description": "Find unused direct dependencies and imports that only resolve through indirect dependencies"
+                },
+                {
+                    "name": "find_module",
+                    "description": "Find which package provides a module, checking the project, the local Elm cache and the registry"
+                },
+                {
+                    "name": "get_project_exports",
+                    "description": "Get the exports of the project's own modules, parsed from source"
+                },
+                {
+                    "name": "get_project_export_docs",
+                    "description": "Get the doc comment of one export of a project module, parsed from source"
+                },
+                {
+                    "name": "list_projects",
+                    "description": "List the Elm projects (elm.json files) in the workspace"
+                },
+                {
+                    "name": "license_report",
+                    "description": "Group the dependency tree by license and flag packages on a deny-list"
+                },
+                {
+                    "name": "get_elm_package_info",
+                    "description": "Get a package's summary, license, exposed modules and dependency ranges from its elm.json"
+                },
+                {
+                    "name": "get_package_releases",
+                    "description": "List a package's published versions with dates and how far behind the project is"
+                },
+                {
+                    "name": "search_registry_docs",
+                    "description": "Full-text search over the docs of every published package, from a local registry docs mirror"
                 }
             ]);
         }
@@ -102,12 +178,32 @@ pub fn display_info(args: &Args) {
             );
             println!("  - get_elm_package_exports: Get all exports from Elm package modules with type signatures (no comments)");
             println!("  - get_elm_package_export_docs: Get documentation for a specific export in an Elm package module");
+            println!("  - search_by_type: Search installed packages for exports matching a type signature");
+            println!("  - get_server_diagnostics: Show server configuration and documentation cache statistics");
+            println!(
+                "  - search_docs: Full-text search over the documentation of all dependencies"
+            );
+            println!("  - get_elm_module_docs: Render a package module's documentation as markdown with @docs expanded");
+            println!("  - diff_package_api: Compare the API of two versions of a package and classify the change as MAJOR/MINOR/PATCH");
+            println!("  - dependency_graph: Build the transitive dependency graph from cached package elm.json files, as JSON, DOT or Mermaid");
+            println!("  - why_dependency: Explain why a package is in elm.json with every path from a direct dependency to it");
+            println!("  - plan_install: Preview the elm.json changes `elm install` would make, or explain the version conflict");
+            println!("  - check_dependency_usage: Find unused direct dependencies and imports that only resolve through indirect dependencies");
+            println!("  - find_module: Find which package provides a module, checking the project, the local Elm cache and the registry");
+            println!("  - get_project_exports: Get the exports of the project's own modules, parsed from source");
+            println!("  - get_project_export_docs: Get the doc comment of one export of a project module, parsed from source");
+            println!("  - list_projects: List the Elm projects (elm.json files) in the workspace");
+            println!("  - license_report: Group the dependency tree by license and flag packages on a deny-list");
+            println!("  - get_elm_package_info: Get a package's summary, license, exposed modules and dependency ranges from its elm.json");
+            println!("  - get_package_releases: List a package's published versions with dates and how far behind the project is");
+            println!("  - search_registry_docs: Full-text search over the docs of every published package, from a local registry docs mirror");
         }
     }
 }
 
 /// handler for `initialize` request from client
-pub async fn initialize(_request: InitializeRequest) -> HandlerResult<InitializeResult> {
+pub async fn initialize(request: InitializeRequest) -> HandlerResult<InitializeResult> {
+    project::set_client_supports_roots(request.capabilities.roots.is_some());
     let result = InitializeResult {
         protocol_version: PROTOCOL_VERSION.to_string(),
         server_info: Implementation {
@@ -155,8 +251,17 @@ pub fn graceful_shutdown() {
     // shutdown server
 }
 
-/// handler for `notifications/initialized` from client
-pub fn notifications_initialized() {}
+/// handler for `notifications/initialized` from client: ask for the client's
+/// roots, returning the request to send
+pub fn notifications_initialized() -> Option<Value> {
+    project::roots_list_request()
+}
+
+/// handler for `notifications/roots/list_changed` from client: ask for the
+/// new roots, returning the request to send
+pub fn notifications_roots_list_changed() -> Option<Value> {
+    project::roots_list_request()
+}
 
 /// handler for `notifications/cancelled` from client
 pub fn notifications_cancelled(_params: CancelledNotification) {
@@ -171,7 +276,15 @@ pub async fn logging_set_level(_request: SetLevelRequest) -> HandlerResult<Loggi
     Ok(LoggingResponse {})
 }
 
+/// The client's workspace roots as last reported to us, used to find elm.json
 pub async fn roots_list(_request: Option<ListRootsRequest>) -> HandlerResult<ListRootsResult> {
-    let response = ListRootsResult { roots: vec![] };
-    Ok(response)
+    let roots = project::roots()
+        .iter()
+        .filter_map(|path| Url::from_file_path(path).ok())
+        .map(|uri| Root {
+            uri: uri.to_string(),
+            name: None,
+        })
+        .collect();
+    Ok(ListRootsResult { roots })
 }